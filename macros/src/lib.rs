@@ -0,0 +1,120 @@
+//! `include_dbus_xml!`, generating zbus bindings from an introspection XML
+//! file at compile time, as an alternative to a build script using
+//! `zbus_xml_gen::Builder`.
+//!
+//! Use it as `zbus_xml_gen_macros::include_dbus_xml!`. `zbus_xml_gen` does not
+//! re-export it, since this crate depends on `zbus_xml_gen` for generating the
+//! code.
+
+use std::path::PathBuf;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Ident, LitStr, Token};
+use zbus_xml::Node;
+use zbus_xml_gen::{generate_from_xml, ContextParams, Dispatch, Mode, Options};
+
+/// Expands to the code `zbus_xml_gen` generates for an XML file, read relative
+/// to `CARGO_MANIFEST_DIR`.
+///
+/// Client proxies are generated by default. Pass `server` for server
+/// interfaces, or `both` for both sides sharing a `types` module, along with
+/// any of `static_dispatch`, `signals`, `notifier`, `serve`, `mock`,
+/// `default_bodies` and `context = "header,connection"`. `names` adds a
+/// `names` module of interface and member name constants on either side:
+///
+/// ```ignore
+/// zbus_xml_gen_macros::include_dbus_xml!("interfaces/foo.xml", server, mock);
+/// ```
+#[proc_macro]
+pub fn include_dbus_xml(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as Input);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Input {
+    path: LitStr,
+    mode: Mode,
+    options: Options,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path: LitStr = input.parse()?;
+        let mut mode = Mode::Client;
+        let mut server_only = None;
+        let mut options = Options::default();
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let flag: Ident = input.parse()?;
+            match flag.to_string().as_str() {
+                "server" => mode = Mode::Server,
+                "both" => mode = Mode::Both,
+                "static_dispatch" => options.dispatch = Dispatch::Static,
+                "signals" => options.signals = true,
+                "notifier" => options.notifier = true,
+                "serve" => options.serve = true,
+                "mock" => options.mock = true,
+                "default_bodies" => options.default_bodies = true,
+                "names" => options.names = true,
+                "context" => {
+                    input.parse::<Token![=]>()?;
+                    let params: LitStr = input.parse()?;
+                    options.context = params
+                        .value()
+                        .parse::<ContextParams>()
+                        .map_err(|err| syn::Error::new(params.span(), err))?;
+                }
+                _ => return Err(syn::Error::new(flag.span(), "unknown option")),
+            }
+            if flag != "server" && flag != "both" && flag != "names" {
+                server_only.get_or_insert(flag);
+            }
+        }
+
+        if let (Mode::Client, Some(flag)) = (mode, server_only) {
+            return Err(syn::Error::new(
+                flag.span(),
+                format!("`{}` requires `server` or `both`", flag),
+            ));
+        }
+        Ok(Self {
+            path,
+            mode,
+            options,
+        })
+    }
+}
+
+fn expand(input: &Input) -> syn::Result<proc_macro2::TokenStream> {
+    let error = |message: String| syn::Error::new(input.path.span(), message);
+
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .ok_or_else(|| error("CARGO_MANIFEST_DIR is not set".to_string()))?;
+    let path = manifest_dir.join(input.path.value());
+    let xml = std::fs::read_to_string(&path)
+        .map_err(|err| error(format!("{}: {}", path.display(), err)))?;
+    Node::from_reader(xml.as_bytes())
+        .map_err(|err| error(format!("{}: invalid D-Bus XML: {}", path.display(), err)))?;
+
+    let code = generate_from_xml(&xml, input.mode, &input.options);
+    let code: proc_macro2::TokenStream = code
+        .parse()
+        .map_err(|err| error(format!("generated code is not valid Rust: {}", err)))?;
+
+    // Recompile when the XML changes.
+    let path = path.to_string_lossy();
+    Ok(quote! {
+        const _: &[u8] = include_bytes!(#path);
+        #code
+    })
+}
@@ -0,0 +1,49 @@
+mod client {
+    zbus_xml_gen_macros::include_dbus_xml!("tests/xml/foo.xml");
+}
+
+mod server {
+    zbus_xml_gen_macros::include_dbus_xml!(
+        "tests/xml/foo.xml",
+        server,
+        static_dispatch,
+        default_bodies,
+        context = "header",
+    );
+}
+
+struct Delegate;
+
+impl server::FooDelegate for Delegate {}
+
+#[test]
+fn include_client_proxy() {
+    fn assert_proxy<P: AsRef<zbus::Proxy<'static>>>() {}
+    assert_proxy::<client::FooProxy<'static>>();
+}
+
+#[test]
+fn include_server_interface() {
+    fn assert_interface<I: zbus::object_server::Interface>(_: &I) {}
+    assert_interface(&server::Foo::new(Delegate));
+}
+
+mod both {
+    zbus_xml_gen_macros::include_dbus_xml!(
+        "tests/xml/foo.xml",
+        both,
+        static_dispatch,
+        default_bodies
+    );
+}
+
+#[test]
+fn include_both_sides() {
+    fn assert_proxy<P: AsRef<zbus::Proxy<'static>>>() {}
+    assert_proxy::<both::client::FooProxy<'static>>();
+
+    struct BothDelegate;
+    impl both::server::FooDelegate for BothDelegate {}
+    fn assert_interface<I: zbus::object_server::Interface>(_: &I) {}
+    assert_interface(&both::server::Foo::new(BothDelegate));
+}
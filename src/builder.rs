@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+
+use crate::codegen::{collect_warnings, generate, GeneratedFile, Mode, Options};
+use crate::sources::{
+    generate_sources_header, is_glob, merged_registrations, read_sources, Source,
+};
+use crate::Error;
+
+/// Generates bindings from a build script into `OUT_DIR`.
+///
+/// Every input is reported with `cargo:rerun-if-changed`, and generator
+/// warnings and errors with `cargo:warning`.
+///
+/// ```no_run
+/// // build.rs
+/// zbus_xml_gen::Builder::new()
+///     .input("interfaces")
+///     .server(true)
+///     .generate()
+///     .unwrap();
+/// ```
+///
+/// The crate then includes `interfaces/foo.xml` as
+/// `include!(concat!(env!("OUT_DIR"), "/foo.rs"));`.
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    inputs: Vec<PathBuf>,
+    mode: Mode,
+    options: Options,
+    combined: Option<String>,
+    out_dir: Option<PathBuf>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an XML file, a directory whose `*.xml` files are all used, or a
+    /// glob pattern such as `interfaces/*1.xml`.
+    pub fn input(mut self, path: impl AsRef<Path>) -> Self {
+        self.inputs.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Generates server traits instead of client proxies.
+    pub fn server(self, server: bool) -> Self {
+        self.mode(if server { Mode::Server } else { Mode::Client })
+    }
+
+    /// Which side of the interfaces to generate code for.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes every input into one file named `file_name`, instead of one
+    /// `<stem>.rs` per input.
+    pub fn combined(mut self, file_name: impl Into<String>) -> Self {
+        self.combined = Some(file_name.into());
+        self
+    }
+
+    /// Writes into `dir` instead of `OUT_DIR`.
+    pub fn out_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Generates the bindings, returning the paths of the generated files.
+    /// Files whose content is unchanged are not rewritten.
+    pub fn generate(&self) -> Result<Vec<PathBuf>, Error> {
+        self.try_generate().map_err(|err| {
+            println!("cargo:warning={}", err);
+            err
+        })
+    }
+
+    fn try_generate(&self) -> Result<Vec<PathBuf>, Error> {
+        let out_dir = match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(Error::NoOutDir)?,
+        };
+        if self.mode == Mode::Client && has_server_options(&self.options) {
+            println!("cargo:warning=server options are ignored when generating client proxies");
+        }
+        if !self.options.allow.is_empty() {
+            println!(
+                "cargo:warning=allow attributes cannot be used in files included with include!(); \
+                 put #[allow(...)] on the enclosing module instead"
+            );
+        }
+
+        for input in self.inputs.iter().filter(|input| !is_glob(input)) {
+            println!("cargo:rerun-if-changed={}", input.display());
+        }
+        let sources = read_sources(&self.inputs)?;
+        if self.inputs.iter().any(|input| is_glob(input)) {
+            for source in &sources {
+                println!("cargo:rerun-if-changed={}", source.path.display());
+            }
+        }
+
+        let files = match &self.combined {
+            Some(file_name) => {
+                let registrations = merged_registrations(&sources)?;
+                let origin = out_dir.join(file_name);
+                vec![GeneratedFile {
+                    name: file_name.clone(),
+                    code: self.generate_code(&origin, &sources, &registrations)?,
+                }]
+            }
+            None => {
+                let names = output_names(&sources)?;
+                sources
+                    .iter()
+                    .zip(names)
+                    .map(|(source, name)| {
+                        let code = self.generate_code(
+                            &source.path,
+                            std::slice::from_ref(source),
+                            &source.registrations(),
+                        )?;
+                        Ok(GeneratedFile { name, code })
+                    })
+                    .collect::<Result<_, Error>>()?
+            }
+        };
+
+        let mut written = Vec::new();
+        for file in files {
+            let path = out_dir.join(&file.name);
+            file.write_to(&out_dir).map_err(|source| Error::Io {
+                path: path.clone(),
+                source,
+            })?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// Generates code for one output from `sources`, reporting warnings and
+    /// invalid code against `origin`.
+    fn generate_code(
+        &self,
+        origin: &Path,
+        sources: &[Source],
+        registrations: &[(String, &zbus_xml::Interface)],
+    ) -> Result<String, Error> {
+        let options = Options {
+            allow: Vec::new(),
+            ..self.options.clone()
+        };
+        let (code, warnings) = collect_warnings(|| generate(registrations, self.mode, &options));
+        for warning in warnings {
+            println!("cargo:warning={}: {}", origin.display(), warning);
+        }
+        let code = code.map_err(|err| Error::InvalidCode {
+            path: origin.to_path_buf(),
+            message: err.to_string(),
+        })?;
+
+        Ok(format!(
+            "{}{}",
+            generate_sources_header(sources, self.mode, &options),
+            code
+        ))
+    }
+}
+
+/// The `<stem>.rs` output file of each source, which must be distinct.
+fn output_names(sources: &[Source]) -> Result<Vec<String>, Error> {
+    let mut names: Vec<String> = Vec::new();
+    for (i, source) in sources.iter().enumerate() {
+        let stem = source.path.file_stem().unwrap_or_default();
+        let name = format!("{}.rs", stem.to_string_lossy());
+        if let Some(other) = names.iter().position(|other| *other == name) {
+            return Err(Error::OutputCollision {
+                file: name,
+                inputs: [sources[other].path.clone(), sources[i].path.clone()],
+            });
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+fn has_server_options(options: &Options) -> bool {
+    options.signals
+        || options.notifier
+        || !options.context.is_empty()
+        || !options.method_context.is_empty()
+        || options.serve
+        || options.mock
+        || options.default_bodies
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+use std::str::FromStr;
+
+use zbus::blocking::{connection, Connection};
+use zbus_xml::Node;
+
+use crate::sources::Source;
+use crate::Error;
+
+/// Interfaces zbus implements on every object itself, left out of
+/// introspected sources.
+const STANDARD_INTERFACES: &[&str] = &[
+    "org.freedesktop.DBus.Introspectable",
+    "org.freedesktop.DBus.Peer",
+    "org.freedesktop.DBus.Properties",
+];
+
+/// A message bus to introspect services on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bus {
+    Session,
+    System,
+    /// A bus at a D-Bus address, e.g. `unix:path=/run/foo/bus`.
+    Address(String),
+}
+
+impl Bus {
+    /// Connects to the bus.
+    pub fn connect(&self) -> Result<Connection, Error> {
+        let connection = match self {
+            Bus::Session => Connection::session(),
+            Bus::System => Connection::system(),
+            Bus::Address(address) => {
+                connection::Builder::address(address.as_str()).and_then(|b| b.build())
+            }
+        };
+        connection.map_err(|source| Error::Bus {
+            object: self.to_string(),
+            source,
+        })
+    }
+}
+
+impl FromStr for Bus {
+    type Err = std::convert::Infallible;
+
+    /// `session`, `system`, or a D-Bus address.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "session" => Bus::Session,
+            "system" => Bus::System,
+            address => Bus::Address(address.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for Bus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bus::Session => write!(f, "session"),
+            Bus::System => write!(f, "system"),
+            Bus::Address(address) => write!(f, "{}", address),
+        }
+    }
+}
+
+/// Introspects `destination` on `bus` from `path` down, see [`introspect`].
+pub fn introspect_bus(bus: &Bus, destination: &str, path: &str) -> Result<Source, Error> {
+    let connection = bus.connect()?;
+    let mut source = introspect(&connection, Some(destination), path)?;
+    source.path = format!("{}:{}", bus, source.path.display()).into();
+    Ok(source)
+}
+
+/// Calls `org.freedesktop.DBus.Introspectable.Introspect` on `path` and,
+/// recursively, on every child node, into one source for the whole object
+/// tree. `destination` is `None` on peer-to-peer connections.
+///
+/// Interfaces zbus implements on every object (`Introspectable`, `Peer` and
+/// `Properties`) are left out.
+pub fn introspect(
+    connection: &Connection,
+    destination: Option<&str>,
+    path: &str,
+) -> Result<Source, Error> {
+    let mut xml = String::new();
+    let mut nodes = Vec::new();
+    let mut pending = vec![path.to_string()];
+    while let Some(object) = pending.pop() {
+        let object_xml = call_introspect(connection, destination, &object)?;
+        let node = Node::from_reader(object_xml.as_bytes()).map_err(|source| Error::Xml {
+            path: object.clone().into(),
+            source,
+        })?;
+
+        let mut children = Vec::new();
+        child_stubs(&node, &object, &mut children);
+        // Reversed, so objects are visited in document order.
+        pending.extend(children.into_iter().rev());
+        xml.push_str(&object_xml);
+        nodes.push((object, node));
+    }
+
+    let name = match destination {
+        Some(destination) => format!("{}:{}", destination, path),
+        None => path.to_string(),
+    };
+    Ok(Source {
+        path: name.into(),
+        xml,
+        nodes,
+        skipped_interfaces: STANDARD_INTERFACES,
+    })
+}
+
+fn call_introspect(
+    connection: &Connection,
+    destination: Option<&str>,
+    path: &str,
+) -> Result<String, Error> {
+    let bus_err = |source| Error::Bus {
+        object: path.to_string(),
+        source,
+    };
+    let reply = connection
+        .call_method(
+            destination,
+            path,
+            Some("org.freedesktop.DBus.Introspectable"),
+            "Introspect",
+            &(),
+        )
+        .map_err(bus_err)?;
+    reply.body().deserialize().map_err(bus_err)
+}
+
+/// The object paths of the empty child nodes under `node` at `path`, which
+/// need introspecting themselves. Children described inline are walked.
+fn child_stubs(node: &Node, path: &str, out: &mut Vec<String>) {
+    for child in node.nodes() {
+        let child_path = match child.name() {
+            Some(name) if name.starts_with('/') => name.to_string(),
+            Some(name) if path == "/" => format!("/{}", name),
+            Some(name) => format!("{}/{}", path, name),
+            None => continue,
+        };
+        if child.interfaces().is_empty() && child.nodes().is_empty() {
+            out.push(child_path);
+        } else {
+            child_stubs(child, &child_path, out);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use zbus_xml_gen::{diff_sources, read_sources, Change, Compatibility};
+
+use super::fail;
+
+#[derive(Args)]
+pub(crate) struct DiffArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+
+    /// Exit with status 1 when there are changes of this kind
+    #[arg(long, value_enum, default_value = "breaking")]
+    fail_on: FailOn,
+
+    /// The previous XML: a file, a directory of `*.xml` files or a glob pattern
+    old: PathBuf,
+
+    /// The new XML, like OLD
+    new: PathBuf,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FailOn {
+    Breaking,
+    Any,
+    Never,
+}
+
+#[derive(serde::Serialize)]
+struct Report<'c> {
+    breaking: bool,
+    changes: &'c [Change],
+}
+
+/// Prints the changes from OLD to NEW and exits: with status 1 if they reach
+/// `--fail-on`, 2 if an input cannot be read.
+pub(crate) fn run(args: &DiffArgs) -> ! {
+    let read =
+        |input: &PathBuf| read_sources(std::slice::from_ref(input)).unwrap_or_else(|err| fail(err));
+    let changes = diff_sources(&read(&args.old), &read(&args.new)).unwrap_or_else(|err| fail(err));
+
+    let breaking = changes
+        .iter()
+        .filter(|change| change.compatibility == Compatibility::Breaking)
+        .count();
+    match args.format {
+        Format::Text => {
+            for change in &changes {
+                println!("{}", change);
+            }
+            if !changes.is_empty() {
+                eprintln!(
+                    "{} breaking, {} compatible change(s)",
+                    breaking,
+                    changes.len() - breaking
+                );
+            }
+        }
+        Format::Json => {
+            let report = Report {
+                breaking: breaking > 0,
+                changes: &changes,
+            };
+            let json = serde_json::to_string_pretty(&report).expect("changes serialize to JSON");
+            println!("{}", json);
+        }
+    }
+
+    let failed = match args.fail_on {
+        FailOn::Breaking => breaking > 0,
+        FailOn::Any => !changes.is_empty(),
+        FailOn::Never => false,
+    };
+    std::process::exit(i32::from(failed));
+}
@@ -0,0 +1,70 @@
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
+use zbus_xml_gen::{
+    generate_docs_from_sources, read_sources, Config, DocFormat, Options, Source, CONFIG_FILE,
+};
+
+use super::fail;
+
+#[derive(Args)]
+pub(crate) struct DocsArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value = "markdown")]
+    format: Format,
+
+    /// Service name used in the `busctl` examples
+    #[arg(long, value_name = "NAME")]
+    service: Option<String>,
+
+    /// Object path used in the `busctl` examples (default: where the XML places each
+    /// interface)
+    #[arg(long, value_name = "PATH")]
+    path: Option<String>,
+
+    /// XML files, directories of `*.xml` files or glob patterns (default: the inputs in
+    /// `zbus-xml-gen.toml`, or stdin)
+    inputs: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Markdown,
+    Html,
+}
+
+/// Prints documentation for the interfaces of the inputs, exiting with
+/// status 2 if an input cannot be read or is invalid.
+pub(crate) fn run(args: &DocsArgs) -> ! {
+    let config_path = Path::new(CONFIG_FILE);
+    let inputs = if args.inputs.is_empty() && config_path.is_file() {
+        let config = Config::from_file(config_path).unwrap_or_else(|err| fail(err));
+        config.inputs(Path::new(""))
+    } else {
+        args.inputs.clone()
+    };
+    let sources = if inputs.is_empty() {
+        let mut xml = String::new();
+        io::stdin()
+            .read_to_string(&mut xml)
+            .unwrap_or_else(|err| fail(format!("<stdin>: {}", err)));
+        vec![Source::from_xml("<stdin>", xml).unwrap_or_else(|err| fail(err))]
+    } else {
+        read_sources(&inputs).unwrap_or_else(|err| fail(err))
+    };
+
+    let format = match args.format {
+        Format::Markdown => DocFormat::Markdown,
+        Format::Html => DocFormat::Html,
+    };
+    let options = Options {
+        default_service: args.service.clone(),
+        default_path: args.path.clone(),
+        ..Options::default()
+    };
+    let docs =
+        generate_docs_from_sources(&sources, format, &options).unwrap_or_else(|err| fail(err));
+    print!("{}", docs);
+    std::process::exit(0);
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use zbus_xml_gen::{format_xml, input_files, Config, XmlFormat, CONFIG_FILE};
+
+use super::fail;
+
+#[derive(Args)]
+pub(crate) struct FmtArgs {
+    /// Print a diff for unformatted inputs and exit with status 1 instead of rewriting
+    /// them
+    #[arg(long)]
+    check: bool,
+
+    /// Order interfaces, child nodes and members by name
+    #[arg(long)]
+    sort: bool,
+
+    /// Spaces per nesting level
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    indent: usize,
+
+    /// XML files, directories of `*.xml` files or glob patterns, rewritten in place
+    /// (default: the inputs in `zbus-xml-gen.toml`, or stdin to stdout)
+    inputs: Vec<PathBuf>,
+}
+
+/// Formats the inputs and exits: with status 1 if `--check` finds unformatted
+/// inputs, 2 if an input cannot be read or is invalid.
+pub(crate) fn run(args: &FmtArgs) -> ! {
+    let format = XmlFormat {
+        indent: args.indent,
+        sort: args.sort,
+    };
+    let format_or_fail = |name: &str, xml: &str| {
+        format_xml(xml, &format).unwrap_or_else(|err| fail(format!("{}: {}", name, err)))
+    };
+
+    let config_path = Path::new(CONFIG_FILE);
+    let inputs = if args.inputs.is_empty() && config_path.is_file() {
+        let config = Config::from_file(config_path).unwrap_or_else(|err| fail(err));
+        config.inputs(Path::new(""))
+    } else {
+        args.inputs.clone()
+    };
+
+    if inputs.is_empty() {
+        let mut xml = String::new();
+        io::stdin()
+            .read_to_string(&mut xml)
+            .unwrap_or_else(|err| fail(format!("<stdin>: {}", err)));
+        let formatted = format_or_fail("<stdin>", &xml);
+        if !args.check {
+            print!("{}", formatted);
+        } else if formatted != xml {
+            print_diff("<stdin>", &xml, &formatted);
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    let mut formatted_all = true;
+    for path in input_files(&inputs).unwrap_or_else(|err| fail(err)) {
+        let name = path.display().to_string();
+        let xml =
+            fs::read_to_string(&path).unwrap_or_else(|err| fail(format!("{}: {}", name, err)));
+        let formatted = format_or_fail(&name, &xml);
+        if formatted == xml {
+            continue;
+        }
+        if args.check {
+            formatted_all = false;
+            print_diff(&name, &xml, &formatted);
+        } else {
+            fs::write(&path, formatted).unwrap_or_else(|err| fail(format!("{}: {}", name, err)));
+        }
+    }
+    if !formatted_all {
+        eprintln!("XML is not formatted; rerun without --check");
+    }
+    std::process::exit(i32::from(!formatted_all));
+}
+
+fn print_diff(name: &str, old: &str, new: &str) {
+    let diff = similar::TextDiff::from_lines(old, new);
+    print!("{}", diff.unified_diff().header(name, name));
+}
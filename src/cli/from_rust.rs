@@ -0,0 +1,56 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use clap::Args;
+use zbus_xml_gen::{generate_xml_from_rust, Options};
+
+use super::fail;
+
+#[derive(Args)]
+pub(crate) struct FromRustArgs {
+    /// Map a Rust type back to a D-Bus signature, e.g. `Point=(ii)` (repeatable)
+    #[arg(long = "type", value_name = "RUST=SIGNATURE", value_parser = parse_type)]
+    types: Vec<(String, String)>,
+
+    /// Rust source file with `#[proxy]` traits or `#[interface]` impls (default: stdin)
+    input: Option<PathBuf>,
+}
+
+fn parse_type(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((rust, signature)) => Ok((rust.to_string(), signature.to_string())),
+        None => Err("expected RUST=SIGNATURE".to_string()),
+    }
+}
+
+/// Prints introspection XML for the interfaces declared in Rust code, exiting
+/// with status 2 if the code cannot be read or mapped to D-Bus types.
+pub(crate) fn run(args: &FromRustArgs) -> ! {
+    let (name, code) = match &args.input {
+        Some(path) => {
+            let name = path.display().to_string();
+            let code =
+                fs::read_to_string(path).unwrap_or_else(|err| fail(format!("{}: {}", name, err)));
+            (name, code)
+        }
+        None => {
+            let mut code = String::new();
+            io::stdin()
+                .read_to_string(&mut code)
+                .unwrap_or_else(|err| fail(format!("<stdin>: {}", err)));
+            ("<stdin>".to_string(), code)
+        }
+    };
+
+    let mut options = Options::default();
+    for (rust, signature) in &args.types {
+        options
+            .type_overrides
+            .insert(signature.clone(), rust.clone());
+    }
+    let xml = generate_xml_from_rust(&code, &options)
+        .unwrap_or_else(|err| fail(format!("{}: {}", name, err)));
+    print!("{}", xml);
+    std::process::exit(0);
+}
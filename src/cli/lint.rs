@@ -0,0 +1,121 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
+use zbus_xml_gen::{input_files, lint_xml, Config, Level, LintLevels, CONFIG_FILE};
+
+use super::fail;
+
+#[derive(Args)]
+pub(crate) struct LintArgs {
+    /// Do not report RULE (a rule name or `all`; repeatable)
+    #[arg(long, short = 'A', value_name = "RULE")]
+    allow: Vec<String>,
+
+    /// Report RULE as a warning (repeatable)
+    #[arg(long, short = 'W', value_name = "RULE")]
+    warn: Vec<String>,
+
+    /// Report RULE as an error (repeatable)
+    #[arg(long, short = 'D', value_name = "RULE")]
+    deny: Vec<String>,
+
+    /// Exit with status 1 when there are findings of this level or worse
+    #[arg(long, value_enum, default_value = "error")]
+    fail_on: FailOn,
+
+    /// List the rules with their default levels
+    #[arg(long, conflicts_with = "inputs")]
+    list: bool,
+
+    /// XML files, directories of `*.xml` files or glob patterns (default: the inputs in
+    /// `zbus-xml-gen.toml`, or stdin)
+    inputs: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FailOn {
+    Warning,
+    Error,
+    Never,
+}
+
+/// Lints the inputs and exits: with status 1 if findings reach `--fail-on`,
+/// 2 if an input cannot be read.
+pub(crate) fn run(args: &LintArgs) -> ! {
+    if args.list {
+        for rule in zbus_xml_gen::Rule::ALL {
+            println!("{:<20} {}", rule.name(), rule.default_level());
+        }
+        std::process::exit(0);
+    }
+
+    let config_path = Path::new(CONFIG_FILE);
+    let config = if config_path.is_file() {
+        Some(Config::from_file(config_path).unwrap_or_else(|err| fail(err)))
+    } else {
+        None
+    };
+
+    let mut levels = match &config {
+        Some(config) => config.lint.levels().unwrap_or_else(|err| fail(err)),
+        None => LintLevels::default(),
+    };
+    let flags = [
+        (&args.allow, Level::Allow),
+        (&args.warn, Level::Warn),
+        (&args.deny, Level::Deny),
+    ];
+    for (rules, level) in flags.iter() {
+        for rule in rules.iter() {
+            levels.set(rule, *level).unwrap_or_else(|err| fail(err));
+        }
+    }
+
+    let inputs = match &config {
+        Some(config) if args.inputs.is_empty() => config.inputs(Path::new("")),
+        _ => args.inputs.clone(),
+    };
+    let files = if inputs.is_empty() {
+        let mut xml = String::new();
+        io::stdin()
+            .read_to_string(&mut xml)
+            .unwrap_or_else(|err| fail(format!("<stdin>: {}", err)));
+        vec![("<stdin>".to_string(), xml)]
+    } else {
+        let paths = input_files(&inputs).unwrap_or_else(|err| fail(err));
+        paths
+            .iter()
+            .map(|path| {
+                let xml = fs::read_to_string(path)
+                    .unwrap_or_else(|err| fail(format!("{}: {}", path.display(), err)));
+                (path.display().to_string(), xml)
+            })
+            .collect()
+    };
+
+    let (mut errors, mut warnings) = (0, 0);
+    for (name, xml) in &files {
+        for diagnostic in lint_xml(xml, &levels) {
+            match diagnostic.line {
+                Some(line) => println!("{}:{}: {}", name, line, diagnostic),
+                None => println!("{}: {}", name, diagnostic),
+            }
+            match diagnostic.level {
+                Level::Deny => errors += 1,
+                _ => warnings += 1,
+            }
+        }
+    }
+    if errors + warnings > 0 {
+        eprintln!("{} error(s), {} warning(s)", errors, warnings);
+    }
+
+    let failed = match args.fail_on {
+        FailOn::Warning => errors + warnings > 0,
+        FailOn::Error => errors > 0,
+        FailOn::Never => false,
+    };
+    std::process::exit(i32::from(failed));
+}
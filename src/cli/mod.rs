@@ -0,0 +1,323 @@
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use zbus_xml_gen::{
+    generate_cli_client_from_sources, generate_from_sources, generate_modules_from_sources,
+    generate_sources_header, ir_from_sources, read_sources, Config, ContextParams, Dispatch,
+    GeneratedFile, Mode, Options, Source, CONFIG_FILE,
+};
+
+mod diff;
+mod docs;
+mod fmt;
+mod from_rust;
+mod lint;
+
+#[derive(Parser)]
+#[command(author, version, about, args_conflicts_with_subcommands = true)]
+#[command(group(ArgGroup::new("server_side").args(["server", "both"])))]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Generate server trait (default is client proxy)
+    #[arg(long)]
+    server: bool,
+
+    /// Generate client proxies and server code sharing a `types` module
+    #[arg(long, conflicts_with = "server")]
+    both: bool,
+
+    /// Generate `Foo<D: FooDelegate>` server structs without `async_trait`
+    #[arg(long, requires = "server_side")]
+    static_dispatch: bool,
+
+    /// Generate a `FooSignalHandle` for emitting signals from delegates
+    #[arg(long, requires = "server_side")]
+    signals: bool,
+
+    /// Generate a `FooNotifier` for emitting property change notifications
+    #[arg(long, requires = "server_side")]
+    notifier: bool,
+
+    /// Context parameters passed to every delegate method (header,connection,object_server,emitter)
+    #[arg(long, requires = "server_side", value_name = "PARAMS")]
+    context: Option<ContextParams>,
+
+    /// Generate a `serve()` function registering interfaces at their XML node paths
+    #[arg(long, requires = "server_side")]
+    serve: bool,
+
+    /// Generate a `MockFooDelegate` for tests
+    #[arg(long, requires = "server_side")]
+    mock: bool,
+
+    /// Give delegate trait methods default bodies returning NotSupported
+    #[arg(long, requires = "server_side")]
+    default_bodies: bool,
+
+    /// Generate a `names` module of `InterfaceName`/`MemberName` constants
+    #[arg(long)]
+    names: bool,
+
+    /// What to generate: Rust code, the `main.rs` of a command-line client with a
+    /// subcommand per method and property, or JSON describing the interfaces and the
+    /// Rust names and types generated for them
+    #[arg(long, value_enum, default_value = "code", conflicts_with_all = ["server_side", "out_dir", "config"])]
+    emit: Emit,
+
+    /// Write `mod.rs` and one module per interface into this directory
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+
+    /// Lint to silence in generated files with `#![allow(...)]` (repeatable)
+    #[arg(long, value_name = "LINT")]
+    allow: Vec<String>,
+
+    /// Generate every output of this configuration file (default: `zbus-xml-gen.toml`
+    /// in the current directory, if there is no input)
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["inputs", "out_dir"])]
+    config: Option<PathBuf>,
+
+    /// Compare the files in `--out-dir` (or of the configuration) with freshly
+    /// generated code instead of writing them; print a diff and exit with status 1 if
+    /// they differ or the output directory holds `.rs` files no longer generated, or
+    /// with status 2 on errors
+    #[arg(long)]
+    check: bool,
+
+    /// Introspect a live service on this bus (session, system or a D-Bus address)
+    /// instead of reading XML
+    #[cfg(feature = "bus")]
+    #[arg(long, value_name = "BUS", requires = "dest", conflicts_with_all = ["inputs", "config"])]
+    bus: Option<zbus_xml_gen::Bus>,
+
+    /// Well-known or unique name of the service to introspect
+    #[cfg(feature = "bus")]
+    #[arg(long, value_name = "NAME", requires = "bus")]
+    dest: Option<String>,
+
+    /// Object path to introspect recursively from
+    #[cfg(feature = "bus")]
+    #[arg(long, value_name = "PATH", requires = "bus", default_value = "/")]
+    path: String,
+
+    /// Input XML files, directories of `*.xml` files or glob patterns, merged into one
+    /// run (defaults to stdin if not provided)
+    inputs: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Emit {
+    Code,
+    CliClient,
+    IrJson,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check introspection XML for invalid data and convention problems
+    Lint(lint::LintArgs),
+    /// Compare two versions of introspection XML and classify each change as breaking
+    /// or compatible for existing clients
+    Diff(diff::DiffArgs),
+    /// Rewrite introspection XML in a canonical layout
+    Fmt(fmt::FmtArgs),
+    /// Print Markdown or HTML documentation for the interfaces
+    Docs(docs::DocsArgs),
+    /// Print introspection XML for the `#[proxy]` traits and `#[interface]` impls of
+    /// Rust code
+    FromRust(from_rust::FromRustArgs),
+}
+
+/// Compares `files` in `dir` with their generated code, or writes them.
+/// When checking, `.rs` files under the `out_dirs` of `dir` that are no
+/// longer generated are reported too.
+fn check_or_write(files: &[GeneratedFile], dir: &Path, out_dirs: &[PathBuf], check: bool) {
+    if check {
+        let mut up_to_date = true;
+        for file in files {
+            let path = dir.join(&file.name);
+            let old = fs::read_to_string(&path).unwrap_or_default();
+            if old != file.code {
+                up_to_date = false;
+                let diff = similar::TextDiff::from_lines(&old, &file.code);
+                let name = path.display().to_string();
+                print!("{}", diff.unified_diff().header(&name, &name));
+            }
+        }
+        let generated: Vec<_> = files.iter().map(|file| dir.join(&file.name)).collect();
+        for out_dir in out_dirs {
+            for path in rs_files(&dir.join(out_dir)) {
+                if !generated.contains(&path) {
+                    up_to_date = false;
+                    let old = fs::read_to_string(&path).unwrap_or_default();
+                    let diff = similar::TextDiff::from_lines(old.as_str(), "");
+                    let name = path.display().to_string();
+                    print!("{}", diff.unified_diff().header(&name, "/dev/null"));
+                    eprintln!("{} is no longer generated; remove it", name);
+                }
+            }
+        }
+        if !up_to_date {
+            eprintln!("Generated code is out of date; rerun without --check");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    fs::create_dir_all(dir).unwrap_or_else(|source| {
+        fail(zbus_xml_gen::Error::Io {
+            path: dir.to_path_buf(),
+            source,
+        })
+    });
+    for file in files {
+        file.write_to(dir).unwrap_or_else(|source| {
+            fail(zbus_xml_gen::Error::Io {
+                path: dir.join(&file.name),
+                source,
+            })
+        });
+    }
+}
+
+/// The `.rs` files under `dir`, recursively, in a stable order.
+fn rs_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut entries: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(Result::ok).map(|e| e.path()).collect(),
+        Err(_) => return files,
+    };
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            files.extend(rs_files(&path));
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Reports `message` and exits with status 2, leaving status 1 for `--check`
+/// finding out-of-date output.
+pub(crate) fn fail(message: impl Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(2);
+}
+
+pub(crate) fn run() {
+    let cli = Cli::parse();
+    match &cli.command {
+        Some(Command::Lint(args)) => lint::run(args),
+        Some(Command::Diff(args)) => diff::run(args),
+        Some(Command::Fmt(args)) => fmt::run(args),
+        Some(Command::Docs(args)) => docs::run(args),
+        Some(Command::FromRust(args)) => from_rust::run(args),
+        None => {}
+    }
+    generate(&cli);
+}
+
+/// Generates code from XML, a configuration file or a live service.
+fn generate(cli: &Cli) {
+    #[cfg(feature = "bus")]
+    let live = cli.bus.is_some();
+    #[cfg(not(feature = "bus"))]
+    let live = false;
+
+    let config = cli.config.clone().or_else(|| {
+        let default = PathBuf::from(CONFIG_FILE);
+        let no_inputs = cli.inputs.is_empty() && cli.out_dir.is_none() && !live;
+        (no_inputs && default.is_file()).then_some(default)
+    });
+    if let Some(path) = config {
+        let root = path.parent().unwrap_or(Path::new(""));
+        let config = Config::from_file(&path).unwrap_or_else(|err| fail(err));
+        let files = config.generate(root).unwrap_or_else(|err| fail(err));
+        let out_dirs: Vec<_> = config
+            .outputs
+            .iter()
+            .filter_map(|output| output.out_dir.clone())
+            .collect();
+        check_or_write(&files, root, &out_dirs, cli.check);
+        return;
+    }
+    if cli.check && cli.out_dir.is_none() {
+        fail("--check requires --out-dir or a configuration file");
+    }
+
+    // Read XML from files, stdin or a live service
+    #[cfg(feature = "bus")]
+    let bus_source = cli.bus.as_ref().map(|bus| {
+        let dest = cli.dest.as_deref().unwrap_or_default();
+        zbus_xml_gen::introspect_bus(bus, dest, &cli.path).unwrap_or_else(|err| fail(err))
+    });
+    #[cfg(not(feature = "bus"))]
+    let bus_source = None;
+
+    let sources = if let Some(source) = bus_source {
+        vec![source]
+    } else if cli.inputs.is_empty() {
+        let mut xml = String::new();
+        io::stdin()
+            .read_to_string(&mut xml)
+            .expect("Failed to read from stdin");
+        vec![Source::from_xml("<stdin>", xml).unwrap_or_else(|err| fail(err))]
+    } else {
+        read_sources(&cli.inputs).unwrap_or_else(|err| fail(err))
+    };
+
+    let mode = if cli.both {
+        Mode::Both
+    } else if cli.server {
+        Mode::Server
+    } else {
+        Mode::Client
+    };
+    let options = Options {
+        dispatch: if cli.static_dispatch {
+            Dispatch::Static
+        } else {
+            Dispatch::Dynamic
+        },
+        signals: cli.signals,
+        notifier: cli.notifier,
+        context: cli.context.unwrap_or_default(),
+        serve: cli.serve,
+        mock: cli.mock,
+        default_bodies: cli.default_bodies,
+        allow: cli.allow.clone(),
+        names: cli.names,
+        ..Options::default()
+    };
+
+    if cli.emit == Emit::IrJson {
+        let ir = ir_from_sources(&sources, &options).unwrap_or_else(|err| fail(err));
+        print!("{}", ir.to_json());
+        return;
+    }
+
+    let header = generate_sources_header(&sources, mode, &options);
+    if let Some(out_dir) = &cli.out_dir {
+        let mut files =
+            generate_modules_from_sources(&sources, mode, &options).unwrap_or_else(|err| fail(err));
+        for file in &mut files {
+            file.code = format!("{}{}", header, file.code);
+        }
+
+        check_or_write(&files, out_dir, &[PathBuf::new()], cli.check);
+        return;
+    }
+
+    // Generate and print code
+    let code = match cli.emit {
+        Emit::CliClient => generate_cli_client_from_sources(&sources, &options),
+        _ => generate_from_sources(&sources, mode, &options),
+    }
+    .unwrap_or_else(|err| fail(err));
+    print!("{}{}", header, code);
+}
@@ -0,0 +1,567 @@
+use std::collections::{BTreeSet, HashSet};
+
+use zbus_xml::{ArgDirection, Interface, Node};
+
+use crate::codegen::{
+    expect_valid, format_code, generate_client_proxies, interfaces_by_path, proxy_member_name,
+    rust_ident, to_pascal_case, to_snake_case, unique_by_name, warn, Options,
+};
+
+/// A `main.rs` for a command-line client of the interfaces: their client
+/// proxies and a `clap` CLI with one subcommand per method and one to get
+/// and one to set each property. Arguments are JSON (strings may also be
+/// given bare) converted to the argument types; replies are printed with
+/// `{:#?}`. With several interfaces, each gets a subcommand of its own.
+///
+/// The program needs the `zbus`, `clap` (with `derive`) and `serde_json`
+/// crates. Members with file descriptor arguments are left out.
+pub fn generate_cli_client_from_xml(xml: &str, options: &Options) -> String {
+    let node = Node::from_reader(std::io::Cursor::new(xml)).expect("Failed to parse D-Bus XML");
+    expect_valid(generate_cli_client(&interfaces_by_path(&node), options))
+}
+
+/// The formatted command-line client for interfaces registered at the given
+/// object paths. Type overrides are not applied, so the program stands alone.
+pub(crate) fn generate_cli_client(
+    registrations: &[(String, &Interface)],
+    options: &Options,
+) -> Result<String, syn::Error> {
+    let interfaces = &unique_by_name(registrations);
+    let options = &options.with_unique_names(interfaces);
+    let mut helpers = BTreeSet::new();
+    let mut code = generate_client_proxies(interfaces, options);
+    code.push_str("\nuse clap::{Parser, Subcommand};\n\n");
+
+    let names: Vec<_> = interfaces.iter().map(|iface| iface.name()).collect();
+    let command_type = if interfaces.len() == 1 {
+        format!("{}Command", options.interface_rust_name(&names[0]))
+    } else {
+        "Command".to_string()
+    };
+    let dest = match &options.default_service {
+        Some(service) => format!(
+            "#[arg(long, default_value = {:?})]\n    dest: String",
+            service
+        ),
+        None => "#[arg(long)]\n    dest: String".to_string(),
+    };
+    let path = options.default_path.as_deref().unwrap_or("/");
+    code.push_str(&format!(
+        r#"/// Calls {} over D-Bus.
+#[derive(Parser)]
+struct Cli {{
+    /// Use the system bus instead of the session bus
+    #[arg(long)]
+    system: bool,
+
+    /// Service to call
+    {},
+
+    /// Object path to call
+    #[arg(long, default_value = {:?})]
+    path: String,
+
+    #[command(subcommand)]
+    command: {},
+}}
+
+"#,
+        names
+            .iter()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        dest,
+        path,
+        command_type
+    ));
+
+    if interfaces.len() > 1 {
+        code.push_str("#[derive(Subcommand)]\nenum Command {\n");
+        for iface in interfaces {
+            let rust_name = options.interface_rust_name(&iface.name());
+            code.push_str(&format!(
+                "    /// {}\n    {} {{\n        #[command(subcommand)]\n        command: {}Command,\n    }},\n",
+                iface.name(),
+                rust_name,
+                rust_name
+            ));
+        }
+        code.push_str("}\n\n");
+    }
+
+    for iface in interfaces {
+        code.push_str(&generate_interface_commands(iface, options, &mut helpers));
+    }
+
+    let dispatch = if interfaces.len() == 1 {
+        format!(
+            "run_{}(&connection, &cli.dest, &cli.path, cli.command)",
+            to_snake_case(&options.interface_rust_name(&names[0]))
+        )
+    } else {
+        let arms: String = interfaces
+            .iter()
+            .map(|iface| {
+                let rust_name = options.interface_rust_name(&iface.name());
+                format!(
+                    "            Command::{} {{ command }} => run_{}(&connection, &cli.dest, &cli.path, command),\n",
+                    rust_name,
+                    to_snake_case(&rust_name)
+                )
+            })
+            .collect();
+        format!("match cli.command {{\n{}        }}", arms)
+    };
+    code.push_str(&format!(
+        r#"fn main() {{
+    let cli = Cli::parse();
+    let connection = if cli.system {{
+        zbus::blocking::Connection::system()
+    }} else {{
+        zbus::blocking::Connection::session()
+    }};
+    let result = connection.map_err(Into::into).and_then(|connection| {{
+        {}
+    }});
+    if let Err(err) = result {{
+        eprintln!("error: {{}}", err);
+        std::process::exit(1);
+    }}
+}}
+"#,
+        dispatch
+    ));
+
+    if !helpers.is_empty() {
+        code.push_str("\ntype ArgResult<T> = std::result::Result<T, String>;\n");
+        for helper in helpers {
+            code.push('\n');
+            code.push_str(helper);
+        }
+    }
+    format_code(&code)
+}
+
+/// The subcommand enum of an interface and the function running them.
+fn generate_interface_commands(
+    iface: &Interface,
+    options: &Options,
+    helpers: &mut BTreeSet<&'static str>,
+) -> String {
+    let rust_name = options.interface_rust_name(&iface.name());
+    let mut variants = String::new();
+    let mut arms = String::new();
+    let mut used_variants = HashSet::new();
+    let mut add_variant = |name: String| {
+        let mut variant = name.clone();
+        let mut count = 2;
+        while !used_variants.insert(variant.clone()) {
+            variant = format!("{}{}", name, count);
+            count += 1;
+        }
+        variant
+    };
+
+    // Proxy method names, as the client proxy names them.
+    let mut used_names = HashSet::new();
+    for method in iface.methods() {
+        let proxy_method = proxy_member_name(&method.name(), &mut used_names, false);
+        let args = method.args();
+        if args.iter().any(|arg| arg.ty().to_string().contains('h')) {
+            warn(format!(
+                "method '{}.{}' takes or returns file descriptors; leaving it out of the CLI",
+                iface.name(),
+                method.name()
+            ));
+            continue;
+        }
+        let variant = add_variant(to_pascal_case(&method.name()));
+        let mut used_fields = HashSet::new();
+        let mut fields = Vec::new();
+        let mut bindings = Vec::new();
+        let mut conversions = String::new();
+        for arg in args
+            .iter()
+            .filter(|arg| arg.direction() != Some(ArgDirection::Out))
+        {
+            let ty = arg.ty().to_string();
+            let base = rust_ident(arg.name().unwrap_or("arg"));
+            let mut field = base.clone();
+            let mut count = 2;
+            while !used_fields.insert(field.clone()) {
+                field = format!("{}_{}", base, count);
+                count += 1;
+            }
+            let label = arg.name().unwrap_or("argument");
+            fields.push(format!(
+                "        /// {} ({})\n        #[arg(allow_hyphen_values = true)]\n        {}: String,\n",
+                label, ty, field
+            ));
+            conversions.push_str(&convert_arg(&ty, &field, helpers));
+            bindings.push(field);
+        }
+        let has_reply = args
+            .iter()
+            .any(|arg| arg.direction() == Some(ArgDirection::Out));
+
+        variants.push_str(&format!(
+            "    /// Call {}.{}\n",
+            iface.name(),
+            method.name()
+        ));
+        if fields.is_empty() {
+            variants.push_str(&format!("    {},\n", variant));
+            arms.push_str(&format!(
+                "        {}Command::{} => {{\n",
+                rust_name, variant
+            ));
+        } else {
+            variants.push_str(&format!("    {} {{\n{}    }},\n", variant, fields.join("")));
+            arms.push_str(&format!(
+                "        {}Command::{} {{ {} }} => {{\n",
+                rust_name,
+                variant,
+                bindings.join(", ")
+            ));
+        }
+        arms.push_str(&conversions);
+        let call = format!("proxy.{}({})?", proxy_method, bindings.join(", "));
+        if has_reply {
+            arms.push_str(&format!(
+                "            let reply = {};\n            println!(\"{{:#?}}\", reply);\n",
+                call
+            ));
+        } else {
+            arms.push_str(&format!("            {};\n", call));
+        }
+        arms.push_str("        }\n");
+    }
+
+    for prop in iface.properties() {
+        let proxy_name = proxy_member_name(&prop.name(), &mut used_names, true);
+        let ty = prop.ty().to_string();
+        if ty.contains('h') {
+            warn(format!(
+                "property '{}.{}' holds file descriptors; leaving it out of the CLI",
+                iface.name(),
+                prop.name()
+            ));
+            continue;
+        }
+        let pascal = to_pascal_case(&prop.name());
+        if prop.access().read() {
+            let variant = add_variant(format!("Get{}", pascal));
+            variants.push_str(&format!(
+                "    /// Get property {}.{}\n    {},\n",
+                iface.name(),
+                prop.name(),
+                variant
+            ));
+            arms.push_str(&format!(
+                "        {}Command::{} => {{\n            let value = proxy.{}()?;\n            println!(\"{{:#?}}\", value);\n        }}\n",
+                rust_name, variant, proxy_name
+            ));
+        }
+        if prop.access().write() {
+            let variant = add_variant(format!("Set{}", pascal));
+            variants.push_str(&format!(
+                "    /// Set property {}.{}\n    {} {{\n        /// The new value ({})\n        #[arg(allow_hyphen_values = true)]\n        value: String,\n    }},\n",
+                iface.name(),
+                prop.name(),
+                variant,
+                ty
+            ));
+            arms.push_str(&format!(
+                "        {}Command::{} {{ value }} => {{\n{}            proxy.set_{}(value)?;\n        }}\n",
+                rust_name,
+                variant,
+                convert_arg(&ty, "value", helpers),
+                proxy_name
+            ));
+        }
+    }
+
+    let snake = to_snake_case(&rust_name);
+    let body = if arms.is_empty() {
+        "    match command {}\n".to_string()
+    } else {
+        format!(
+            "    let proxy = {}ProxyBlocking::builder(connection)\n        .destination(dest)?\n        .path(path)?\n        .build()?;\n    match command {{\n{}    }}\n    Ok(())\n",
+            rust_name, arms
+        )
+    };
+    format!(
+        r#"/// Commands for {iface}.
+#[derive(Subcommand)]
+enum {rust_name}Command {{
+{variants}}}
+
+fn run_{snake}(
+    connection: &zbus::blocking::Connection,
+    dest: &str,
+    path: &str,
+    command: {rust_name}Command,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {{
+{body}}}
+
+"#,
+        iface = iface.name(),
+        rust_name = rust_name,
+        variants = variants,
+        snake = snake,
+        body = body
+    )
+}
+
+/// Statements rebinding the `String` command-line argument `field` to the
+/// Rust type of D-Bus type `ty`. Strings are taken as they are, anything else
+/// is JSON.
+fn convert_arg(ty: &str, field: &str, helpers: &mut BTreeSet<&'static str>) -> String {
+    if ty == "s" {
+        return String::new();
+    }
+    helpers.insert(JSON_ARG);
+    format!(
+        "            let {field} = json_arg(&{field});\n            let {field} = {};\n",
+        convert(ty, &format!("&{}", field), 0, helpers),
+        field = field
+    )
+}
+
+/// An expression converting the `&serde_json::Value` expression `json` to
+/// the Rust type of the complete D-Bus type `ty`.
+fn convert(ty: &str, json: &str, depth: usize, helpers: &mut BTreeSet<&'static str>) -> String {
+    let int = |rust: &str, helpers: &mut BTreeSet<&'static str>| {
+        helpers.insert(JSON_INT);
+        format!("json_int::<{}>({})?", rust, json)
+    };
+    match ty.as_bytes()[0] {
+        b'y' => int("u8", helpers),
+        b'n' => int("i16", helpers),
+        b'q' => int("u16", helpers),
+        b'i' => int("i32", helpers),
+        b'u' => int("u32", helpers),
+        b'x' => int("i64", helpers),
+        b't' => int("u64", helpers),
+        b'b' => {
+            helpers.insert(JSON_BOOL);
+            format!("json_bool({})?", json)
+        }
+        b'd' => {
+            helpers.insert(JSON_F64);
+            format!("json_f64({})?", json)
+        }
+        b's' => {
+            helpers.insert(JSON_STRING);
+            format!("json_string({})?", json)
+        }
+        b'o' => {
+            helpers.insert(JSON_STRING);
+            format!(
+                "zbus::zvariant::ObjectPath::try_from(json_string({})?).map_err(|err| err.to_string())?",
+                json
+            )
+        }
+        b'g' => {
+            helpers.insert(JSON_STRING);
+            format!(
+                "zbus::zvariant::Signature::try_from(json_string({})?.as_str()).map_err(|err| err.to_string())?",
+                json
+            )
+        }
+        b'v' => {
+            helpers.insert(JSON_VARIANT);
+            format!("json_variant({})?", json)
+        }
+        b'a' if ty.as_bytes()[1] == b'{' => {
+            helpers.insert(JSON_OBJECT);
+            let inner = &ty[2..ty.len() - 1];
+            let key_ty = &inner[..1];
+            let value_ty = &inner[1..];
+            let (key, value) = (format!("key{}", depth), format!("value{}", depth));
+            format!(
+                "json_object({json})?.iter().map(|({key}, {value})| -> ArgResult<_> {{ Ok(({}, {})) }}).collect::<ArgResult<std::collections::HashMap<_, _>>>()?",
+                convert_key(key_ty, &key),
+                convert(value_ty, &value, depth + 1, helpers),
+                json = json,
+                key = key,
+                value = value
+            )
+        }
+        b'a' => {
+            helpers.insert(JSON_ARRAY);
+            let item = format!("item{}", depth);
+            format!(
+                "json_array({})?.iter().map(|{}| -> ArgResult<_> {{ Ok({}) }}).collect::<ArgResult<Vec<_>>>()?",
+                json,
+                item,
+                convert(&ty[1..], &item, depth + 1, helpers)
+            )
+        }
+        b'(' => {
+            helpers.insert(JSON_STRUCT);
+            let field_types = split_types(&ty[1..ty.len() - 1]);
+            let fields = format!("fields{}", depth);
+            let values: Vec<_> = field_types
+                .iter()
+                .enumerate()
+                .map(|(i, field_ty)| {
+                    convert(field_ty, &format!("&{}[{}]", fields, i), depth + 1, helpers)
+                })
+                .collect();
+            // Like `dbus_type_to_rust`, a one-field struct is its field.
+            let value = match values.as_slice() {
+                [value] => value.clone(),
+                _ => format!("({})", values.join(", ")),
+            };
+            format!(
+                "{{ let {} = json_struct({}, {})?; {} }}",
+                fields,
+                json,
+                values.len(),
+                value
+            )
+        }
+        _ => unreachable!("file descriptors are left out"),
+    }
+}
+
+/// An expression converting the `&String` dict key `key` to the Rust type of
+/// the basic D-Bus type `ty`.
+fn convert_key(ty: &str, key: &str) -> String {
+    match ty {
+        "s" => format!("{}.clone()", key),
+        "o" => format!(
+            "zbus::zvariant::ObjectPath::try_from({}.clone()).map_err(|err| err.to_string())?",
+            key
+        ),
+        "g" => format!(
+            "zbus::zvariant::Signature::try_from({}.as_str()).map_err(|err| err.to_string())?",
+            key
+        ),
+        _ => {
+            let rust = match ty {
+                "y" => "u8",
+                "b" => "bool",
+                "n" => "i16",
+                "q" => "u16",
+                "i" => "i32",
+                "u" => "u32",
+                "x" => "i64",
+                "t" => "u64",
+                _ => "f64",
+            };
+            format!(
+                "{}.parse::<{}>().map_err(|err| err.to_string())?",
+                key, rust
+            )
+        }
+    }
+}
+
+/// The complete types of a signature, e.g. `s`, `a{sv}` and `(ii)` for
+/// `sa{sv}(ii)`.
+fn split_types(signature: &str) -> Vec<&str> {
+    let mut types = Vec::new();
+    let (mut start, mut depth) = (0, 0);
+    for (i, c) in signature.char_indices() {
+        match c {
+            'a' => continue,
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            types.push(&signature[start..=i]);
+            start = i + 1;
+        }
+    }
+    types
+}
+
+const JSON_ARG: &str = r#"/// A command-line argument as JSON, or as a JSON string if it is not JSON.
+fn json_arg(arg: &str) -> serde_json::Value {
+    serde_json::from_str(arg).unwrap_or_else(|_| serde_json::Value::String(arg.to_string()))
+}
+"#;
+
+const JSON_INT: &str = r#"fn json_int<T: TryFrom<i64> + TryFrom<u64>>(json: &serde_json::Value) -> ArgResult<T> {
+    let converted = match (json.as_u64(), json.as_i64()) {
+        (Some(n), _) => T::try_from(n).ok(),
+        (None, Some(n)) => T::try_from(n).ok(),
+        (None, None) => return Err(format!("expected an integer, got {}", json)),
+    };
+    converted.ok_or_else(|| format!("{} is out of range", json))
+}
+"#;
+
+const JSON_BOOL: &str = r#"fn json_bool(json: &serde_json::Value) -> ArgResult<bool> {
+    json.as_bool()
+        .ok_or_else(|| format!("expected true or false, got {}", json))
+}
+"#;
+
+const JSON_F64: &str = r#"fn json_f64(json: &serde_json::Value) -> ArgResult<f64> {
+    json.as_f64()
+        .ok_or_else(|| format!("expected a number, got {}", json))
+}
+"#;
+
+const JSON_STRING: &str = r#"fn json_string(json: &serde_json::Value) -> ArgResult<String> {
+    json.as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("expected a string, got {}", json))
+}
+"#;
+
+const JSON_ARRAY: &str = r#"fn json_array(json: &serde_json::Value) -> ArgResult<&Vec<serde_json::Value>> {
+    json.as_array()
+        .ok_or_else(|| format!("expected an array, got {}", json))
+}
+"#;
+
+const JSON_OBJECT: &str = r#"fn json_object(
+    json: &serde_json::Value,
+) -> ArgResult<&serde_json::Map<String, serde_json::Value>> {
+    json.as_object()
+        .ok_or_else(|| format!("expected an object, got {}", json))
+}
+"#;
+
+const JSON_STRUCT: &str = r#"fn json_struct(json: &serde_json::Value, len: usize) -> ArgResult<&[serde_json::Value]> {
+    match json.as_array() {
+        Some(fields) if fields.len() == len => Ok(fields),
+        _ => Err(format!("expected an array of {} fields, got {}", len, json)),
+    }
+}
+"#;
+
+const JSON_VARIANT: &str = r#"/// A variant of the D-Bus type JSON maps to most naturally: arrays are `av`
+/// and objects `a{sv}`.
+fn json_variant(json: &serde_json::Value) -> ArgResult<zbus::zvariant::Value<'static>> {
+    use zbus::zvariant::Value;
+    Ok(match json {
+        serde_json::Value::Null => return Err("null has no D-Bus type".to_string()),
+        serde_json::Value::Bool(b) => Value::from(*b),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => Value::from(n),
+            (None, Some(n)) => Value::from(n),
+            (None, None) => Value::from(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::from(s.clone()),
+        serde_json::Value::Array(items) => {
+            let items: Vec<Value<'static>> =
+                items.iter().map(json_variant).collect::<ArgResult<_>>()?;
+            Value::from(items)
+        }
+        serde_json::Value::Object(map) => {
+            let dict: std::collections::HashMap<String, Value<'static>> = map
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), json_variant(value)?)))
+                .collect::<ArgResult<_>>()?;
+            Value::from(dict)
+        }
+    })
+}
+"#;
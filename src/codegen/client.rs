@@ -2,64 +2,91 @@ use std::collections::HashSet;
 
 use crate::codegen::dbus_type_to_rust;
 use crate::codegen::dedup_trait_name;
-use crate::codegen::escape_rust_keyword;
-use crate::codegen::to_snake_case;
+use crate::codegen::rust_ident;
+use crate::codegen::unique_interfaces;
+use crate::codegen::Options;
+use crate::codegen::{expect_valid, format_code};
 
 use zbus_xml::{ArgDirection, Interface, Method, Node, Property, PropertyAccess};
 
 pub fn generate_client_proxies_from_xml(xml: &str) -> String {
     let cursor = std::io::Cursor::new(xml);
     let node = Node::from_reader(cursor).expect("Failed to parse D-Bus XML");
-    let mut code = String::new();
-
-    code.push_str(
-        r#"use zbus::proxy;
-use zbus::Result;
+    let code = generate_client_proxies(&unique_interfaces(&node), &Options::default());
+    expect_valid(format_code(&code))
+}
 
-"#,
-    );
+/// Client proxies for the given interfaces.
+pub(crate) fn generate_client_proxies(interfaces: &[&Interface], options: &Options) -> String {
+    let mut code = generate_client_imports();
+    code.push('\n');
 
-    for iface in node.interfaces() {
-        code.push_str(&generate_client_proxy(iface));
+    for iface in interfaces {
+        code.push_str(&generate_client_proxy(iface, options));
     }
 
     code
 }
 
-fn generate_client_proxy(interface: &Interface) -> String {
+pub(super) fn generate_client_imports() -> String {
+    "use zbus::proxy;\nuse zbus::Result;\n".to_string()
+}
+
+pub(super) fn generate_client_proxy(interface: &Interface, options: &Options) -> String {
     let mut code = String::new();
 
     let iface_name = interface.name();
-    code.push_str(&format!(
-        r#"#[proxy(interface = "{}" , assume_defaults = true)]
-"#,
-        iface_name
-    ));
+    let mut proxy_args = format!("interface = {:?}", iface_name.as_str());
+    if let Some(service) = &options.default_service {
+        proxy_args.push_str(&format!(", default_service = {:?}", service));
+    }
+    if let Some(path) = &options.default_path {
+        proxy_args.push_str(&format!(", default_path = {:?}", path));
+    }
+    if options.default_service.is_none() && options.default_path.is_none() {
+        proxy_args.push_str(", assume_defaults = true");
+    }
+    code.push_str(&format!("#[proxy({})]\n", proxy_args));
 
-    let trait_name = iface_name.rsplit('.').next().unwrap_or("Iface");
+    let trait_name = options.interface_rust_name(iface_name.as_str());
     code.push_str(&format!("pub trait {} {{\n", trait_name));
 
     let mut used_names = HashSet::new();
     for method in interface.methods() {
-        code.push_str(&codegen_method(method, &mut used_names));
+        code.push_str(&codegen_method(method, &mut used_names, options));
     }
     for prop in interface.properties() {
-        code.push_str(&codegen_property(prop, &mut used_names));
+        code.push_str(&codegen_property(prop, &mut used_names, options));
     }
     for signal in interface.signals() {
-        code.push_str(&codegen_signal(signal, &mut used_names));
+        code.push_str(&codegen_signal(signal, &mut used_names, options));
     }
 
     code.push_str("}\n");
     code
 }
 
-fn codegen_signal(signal: &zbus_xml::Signal, used_names: &mut HashSet<String>) -> String {
-    let rust_name = dedup_trait_name(&to_snake_case(&signal.name()), used_names, false);
+/// The proxy trait method for a member, sanitized like server names and
+/// unique among `used_names`. Methods, properties and signals take their
+/// names in that order.
+pub(crate) fn proxy_member_name(
+    name: &str,
+    used_names: &mut HashSet<String>,
+    is_property: bool,
+) -> String {
+    dedup_trait_name(&rust_ident(name), used_names, is_property)
+}
+
+fn codegen_signal(
+    signal: &zbus_xml::Signal,
+    used_names: &mut HashSet<String>,
+    options: &Options,
+) -> String {
+    let rust_name = proxy_member_name(&signal.name(), used_names, false);
     let types: Vec<_> = signal
         .args()
         .iter()
-        .map(|arg| dbus_type_to_rust(&arg.ty().to_string()))
+        .map(|arg| dbus_type_to_rust(&arg.ty().to_string(), &options.type_overrides))
         .collect();
 
     let stream_type = match types.len() {
@@ -69,17 +96,17 @@ fn codegen_signal(signal: &zbus_xml::Signal, used_names: &mut HashSet<String>) -
     };
 
     format!(
-        r#"  #[zbus(signal)]
-  fn {rust_name}(&self) -> zbus::Result<zbus::SignalStream<{stream_type}>>;
+        r#"    #[zbus(signal)]
+    fn {rust_name}(&self) -> zbus::Result<zbus::SignalStream<{stream_type}>>;
 
 "#,
     )
 }
 
-fn codegen_method(method: &Method, used_names: &mut HashSet<String>) -> String {
+fn codegen_method(method: &Method, used_names: &mut HashSet<String>, options: &Options) -> String {
     let rust_method = render_rust_method_name(method, used_names);
-    let args = render_method_args(method);
-    let ret_ty = render_method_return_type(method);
+    let args = render_method_args(method, options);
+    let ret_ty = render_method_return_type(method, options);
     let needs_zbus_name_attr = method_needs_zbus_name_attr(method, &rust_method);
 
     let mut s = String::new();
@@ -91,37 +118,37 @@ fn codegen_method(method: &Method, used_names: &mut HashSet<String>) -> String {
 }
 
 fn render_rust_method_name(method: &Method, used_names: &mut HashSet<String>) -> String {
-    let base_name = to_snake_case(&method.name());
-    dedup_trait_name(&base_name, used_names, false)
+    proxy_member_name(&method.name(), used_names, false)
 }
 
-fn render_method_args(method: &Method) -> String {
+fn render_method_args(method: &Method, options: &Options) -> String {
     let mut used_arg_names = HashSet::new();
     method
         .args()
         .iter()
         .filter(|arg| matches!(arg.direction(), Some(ArgDirection::In)))
-        .map(|arg| render_arg(arg, &mut used_arg_names))
+        .map(|arg| render_arg(arg, &mut used_arg_names, options))
         .collect::<Vec<_>>()
         .join(", ")
 }
 
-fn render_arg(arg: &zbus_xml::Arg, used_arg_names: &mut HashSet<String>) -> String {
-    let mut arg_name = arg
-        .name()
-        .map(to_snake_case)
-        .unwrap_or_else(|| "arg".to_string());
-    arg_name = escape_rust_keyword(&arg_name);
+fn render_arg(
+    arg: &zbus_xml::Arg,
+    used_arg_names: &mut HashSet<String>,
+    options: &Options,
+) -> String {
+    let mut arg_name = rust_ident(arg.name().unwrap_or("arg"));
     let orig_name = arg_name.clone();
     let mut count = 2;
     while !used_arg_names.insert(arg_name.clone()) {
         arg_name = format!("{}_{}", orig_name, count);
         count += 1;
     }
-    format!("{}: {}", arg_name, dbus_type_to_rust(&arg.ty().to_string()))
+    let ty = dbus_type_to_rust(&arg.ty().to_string(), &options.type_overrides);
+    format!("{}: {}", arg_name, ty)
 }
 
-fn render_method_return_type(method: &Method) -> String {
+fn render_method_return_type(method: &Method, options: &Options) -> String {
     let out_args: Vec<_> = method
         .args()
         .iter()
@@ -129,11 +156,11 @@ fn render_method_return_type(method: &Method) -> String {
         .collect();
     match out_args.len() {
         0 => "()".to_string(),
-        1 => dbus_type_to_rust(&out_args[0].ty().to_string()),
+        1 => dbus_type_to_rust(&out_args[0].ty().to_string(), &options.type_overrides),
         _ => {
             let types = out_args
                 .iter()
-                .map(|arg| dbus_type_to_rust(&arg.ty().to_string()))
+                .map(|arg| dbus_type_to_rust(&arg.ty().to_string(), &options.type_overrides))
                 .collect::<Vec<_>>();
             format!("({})", types.join(", "))
         }
@@ -141,24 +168,24 @@ fn render_method_return_type(method: &Method) -> String {
 }
 
 fn method_needs_zbus_name_attr(method: &Method, rust_method: &str) -> bool {
-    rust_method != escape_rust_keyword(&to_snake_case(&method.name()))
+    rust_method != rust_ident(&method.name())
 }
 
 fn render_zbus_name_attr(method: &Method) -> String {
-    format!(r#"  #[zbus(name = "{}")]\n"#, method.name())
+    format!("    #[zbus(name = {:?})]\n", method.name().as_str())
 }
 
 fn render_method_signature(rust_method: &str, args: &str, ret_ty: &str) -> String {
     if args.is_empty() {
         format!(
-            r#"  fn {}(&self) -> zbus::Result<{}>;
+            r#"    fn {}(&self) -> zbus::Result<{}>;
 
 "#,
             rust_method, ret_ty
         )
     } else {
         format!(
-            r#"  fn {}(&self, {}) -> zbus::Result<{}>;
+            r#"    fn {}(&self, {}) -> zbus::Result<{}>;
 
 "#,
             rust_method, args, ret_ty
@@ -166,9 +193,13 @@ fn render_method_signature(rust_method: &str, args: &str, ret_ty: &str) -> Strin
     }
 }
 
-fn codegen_property(prop: &Property, used_names: &mut HashSet<String>) -> String {
+fn codegen_property(
+    prop: &Property,
+    used_names: &mut HashSet<String>,
+    options: &Options,
+) -> String {
     let rust_name = render_property_rust_name(prop, used_names);
-    let rust_type = render_property_type(prop);
+    let rust_type = render_property_type(prop, options);
     match prop.access() {
         PropertyAccess::Read => render_property_getter(&rust_name, &rust_type),
         PropertyAccess::Write => render_property_setter(&rust_name, &rust_type),
@@ -181,18 +212,17 @@ fn codegen_property(prop: &Property, used_names: &mut HashSet<String>) -> String
 }
 
 fn render_property_rust_name(prop: &Property, used_names: &mut HashSet<String>) -> String {
-    let base_name = to_snake_case(&prop.name());
-    dedup_trait_name(&base_name, used_names, true)
+    proxy_member_name(&prop.name(), used_names, true)
 }
 
-fn render_property_type(prop: &Property) -> String {
-    dbus_type_to_rust(&prop.ty().to_string())
+fn render_property_type(prop: &Property, options: &Options) -> String {
+    dbus_type_to_rust(&prop.ty().to_string(), &options.type_overrides)
 }
 
 fn render_property_getter(rust_name: &str, rust_type: &str) -> String {
     format!(
-        r#"  #[zbus(property)]
-  fn {}(&self) -> zbus::Result<{}>;
+        r#"    #[zbus(property)]
+    fn {}(&self) -> zbus::Result<{}>;
 
 "#,
         rust_name, rust_type
@@ -201,8 +231,8 @@ fn render_property_getter(rust_name: &str, rust_type: &str) -> String {
 
 fn render_property_setter(rust_name: &str, rust_type: &str) -> String {
     format!(
-        r#"  #[zbus(property)]
-  fn set_{}(&self, value: {}) -> zbus::Result<()>;
+        r#"    #[zbus(property)]
+    fn set_{}(&self, value: {}) -> zbus::Result<()>;
 
 "#,
         rust_name, rust_type
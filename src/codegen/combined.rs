@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use zbus_xml::{Arg, Interface, Node};
+
+use crate::codegen::{
+    dbus_type_to_rust, expect_valid, format_code, generate_allow_attributes,
+    generate_client_proxies, generate_name_constants, generate_server_interfaces,
+    interfaces_by_path, rust_ident, to_pascal_case, unique_by_name, Mode, Options,
+};
+
+/// Code for the given side of the interfaces. `options` only affects server
+/// code.
+pub fn generate_from_xml(xml: &str, mode: Mode, options: &Options) -> String {
+    let node = Node::from_reader(std::io::Cursor::new(xml)).expect("Failed to parse D-Bus XML");
+    expect_valid(generate(&interfaces_by_path(&node), mode, options))
+}
+
+/// Formatted code for interfaces registered at the given object paths.
+pub(crate) fn generate(
+    registrations: &[(String, &Interface)],
+    mode: Mode,
+    options: &Options,
+) -> Result<String, syn::Error> {
+    let options = &options.with_unique_names(&unique_by_name(registrations));
+    let code = match effective_mode(registrations, mode, options) {
+        Mode::Client => generate_client_proxies(&unique_by_name(registrations), options),
+        Mode::Server => generate_server_interfaces(registrations, options),
+        Mode::Both => generate_combined(registrations, mode, options),
+    };
+    let names = if options.names {
+        let constants = generate_name_constants(&unique_by_name(registrations), options);
+        format!("\npub mod names {{\n{}}}\n", indent(&constants))
+    } else {
+        String::new()
+    };
+    format_code(&format!(
+        "{}{}{}",
+        generate_allow_attributes(options),
+        code,
+        names
+    ))
+}
+
+fn generate_combined(
+    registrations: &[(String, &Interface)],
+    mode: Mode,
+    options: &Options,
+) -> String {
+    let (side_options, types) = shared_types(&unique_by_name(registrations), options);
+    let prelude = if types.is_empty() {
+        ""
+    } else {
+        "use super::types::*;\n"
+    };
+    let client_side = side_registrations(registrations, Mode::Client, mode, options);
+    let server_side = side_registrations(registrations, Mode::Server, mode, options);
+    let client = generate_client_proxies(&unique_by_name(&client_side), &side_options);
+    let server = generate_server_interfaces(&server_side, &side_options);
+
+    format!(
+        "pub mod types {{\n{}}}\n\npub mod client {{\n{}}}\n\npub mod server {{\n{}}}\n",
+        indent(&types),
+        indent(&format!("{}{}", prelude, client)),
+        indent(&format!("{}{}", prelude, server)),
+    )
+}
+
+/// The layout of the output: `mode`, unless [`Options::interface_modes`] puts
+/// every interface in another mode, or interfaces in different modes (then
+/// [`Mode::Both`]).
+pub(super) fn effective_mode(
+    registrations: &[(String, &Interface)],
+    mode: Mode,
+    options: &Options,
+) -> Mode {
+    let interfaces = unique_by_name(registrations);
+    let mut modes = interfaces
+        .iter()
+        .map(|iface| interface_mode(iface, mode, options));
+    match modes.next() {
+        Some(first) if modes.all(|m| m == first) => first,
+        Some(_) => Mode::Both,
+        None => mode,
+    }
+}
+
+fn interface_mode(interface: &Interface, mode: Mode, options: &Options) -> Mode {
+    let name = interface.name();
+    options
+        .interface_modes
+        .get(name.as_str())
+        .copied()
+        .unwrap_or(mode)
+}
+
+/// The registrations of the interfaces generated for `side` (client or
+/// server) in the [`Mode::Both`] layout.
+pub(super) fn side_registrations<'r, 'a>(
+    registrations: &[(String, &'r Interface<'a>)],
+    side: Mode,
+    mode: Mode,
+    options: &Options,
+) -> Vec<(String, &'r Interface<'a>)> {
+    registrations
+        .iter()
+        .filter(|(_, iface)| {
+            let iface_mode = interface_mode(iface, mode, options);
+            iface_mode == side || iface_mode == Mode::Both
+        })
+        .cloned()
+        .collect()
+}
+
+/// Shared type aliases for the [`Mode::Both`] layout, and `options` with the
+/// aliased signatures added to [`Options::type_overrides`], so both sides use
+/// the aliases.
+pub(super) fn shared_types(interfaces: &[&Interface], options: &Options) -> (Options, String) {
+    let (mut named, types) = generate_shared_types(interfaces, &options.type_overrides);
+    named.extend(options.type_overrides.clone());
+    let options = Options {
+        type_overrides: named,
+        ..options.clone()
+    };
+    (options, types)
+}
+
+/// Type aliases for the struct and dict signatures used by `interfaces`, named
+/// after the first member and argument using them, along with the Rust type
+/// each signature maps to. Signatures in `overrides` get no alias.
+fn generate_shared_types(
+    interfaces: &[&Interface],
+    overrides: &BTreeMap<String, String>,
+) -> (BTreeMap<String, String>, String) {
+    let mut named = BTreeMap::new();
+    let mut used_names = HashSet::new();
+    let mut out = String::new();
+
+    let mut add = |ty: String, name: String| {
+        let compound = ty.contains('(') || ty.contains('{');
+        if !compound || named.contains_key(&ty) || overrides.contains_key(&ty) {
+            return;
+        }
+        let mut alias = name.clone();
+        let mut idx = 2;
+        while !used_names.insert(alias.clone()) {
+            alias = format!("{}{}", name, idx);
+            idx += 1;
+        }
+
+        let rust = dbus_type_to_rust(&ty, overrides);
+        writeln!(out, "/// D-Bus type `{}`.", ty).unwrap();
+        if rust.contains("'_") {
+            writeln!(
+                out,
+                "pub type {}<'a> = {};",
+                alias,
+                rust.replace("'_", "'a")
+            )
+            .unwrap();
+            named.insert(ty, format!("{}<'_>", alias));
+        } else {
+            writeln!(out, "pub type {} = {};", alias, rust).unwrap();
+            named.insert(ty, alias);
+        }
+    };
+
+    for iface in interfaces {
+        for method in iface.methods() {
+            for (idx, arg) in method.args().iter().enumerate() {
+                add(arg.ty().to_string(), arg_alias(&method.name(), arg, idx));
+            }
+        }
+        for signal in iface.signals() {
+            for (idx, arg) in signal.args().iter().enumerate() {
+                add(arg.ty().to_string(), arg_alias(&signal.name(), arg, idx));
+            }
+        }
+        for prop in iface.properties() {
+            let name = format!("{}Value", to_pascal_case(&rust_ident(&prop.name())));
+            add(prop.ty().to_string(), name);
+        }
+    }
+
+    (named, out)
+}
+
+fn arg_alias(member: &str, arg: &Arg, idx: usize) -> String {
+    let arg_name = match arg.name() {
+        Some(name) => to_pascal_case(&rust_ident(name)),
+        None => format!("Arg{}", idx),
+    };
+    format!("{}{}", to_pascal_case(member), arg_name)
+}
+
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| match line {
+            "" => "\n".to_string(),
+            line => format!("    {}\n", line),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_type_aliases() {
+        let xml = r#"
+<node>
+  <interface name="org.example.Foo">
+    <method name="GetItems"><arg name="items" type="a(su)" direction="out"/></method>
+    <signal name="ItemsChanged"><arg name="items" type="a(su)"/></signal>
+    <property name="Hints" type="a{sv}" access="read"/>
+    <property name="Count" type="u" access="read"/>
+  </interface>
+</node>
+"#;
+        let node = Node::from_reader(xml.as_bytes()).unwrap();
+        let interfaces = unique_by_name(&interfaces_by_path(&node));
+        let (named, types) = generate_shared_types(&interfaces, &BTreeMap::new());
+
+        assert_eq!(named.len(), 2);
+        assert_eq!(named["a(su)"], "GetItemsItems");
+        assert_eq!(named["a{sv}"], "HintsValue<'_>");
+        assert!(types.contains("pub type GetItemsItems = Vec<(String, u32)>;"));
+        assert!(types.contains(
+            "pub type HintsValue<'a> = std::collections::HashMap<String, zbus::zvariant::Value<'a>>;"
+        ));
+    }
+}
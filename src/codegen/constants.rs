@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use zbus_xml::Interface;
+
+use crate::codegen::{rust_ident, Options};
+
+/// The body of the `names` module for [`Options::names`]: a module per
+/// interface, named after its Rust name, with an `INTERFACE` constant and one
+/// constant per method, signal and property name.
+pub(super) fn generate_name_constants(interfaces: &[&Interface], options: &Options) -> String {
+    let mut code = "use zbus::names::{InterfaceName, MemberName};\n".to_string();
+    for iface in interfaces {
+        let name = iface.name();
+        write!(
+            code,
+            "\n/// Names of `{0}`.\npub mod {1} {{\n    use super::*;\n\n    \
+             pub const INTERFACE: InterfaceName<'static> =\n        \
+             InterfaceName::from_static_str_unchecked({0:?});\n",
+            name.as_str(),
+            rust_ident(&options.interface_rust_name(&name))
+        )
+        .unwrap();
+
+        // A name used by several members (e.g. a method and a property) gets
+        // one constant.
+        let members = iface
+            .methods()
+            .iter()
+            .map(|m| (m.name().to_string(), "Method"))
+            .chain(
+                iface
+                    .properties()
+                    .iter()
+                    .map(|p| (p.name().to_string(), "Property")),
+            )
+            .chain(
+                iface
+                    .signals()
+                    .iter()
+                    .map(|s| (s.name().to_string(), "Signal")),
+            );
+        let mut seen = HashSet::new();
+        let mut used_consts: HashSet<_> = ["INTERFACE".to_string()].iter().cloned().collect();
+        for (member, kind) in members {
+            if !seen.insert(member.clone()) {
+                continue;
+            }
+            let base = rust_ident(&member).trim_end_matches('_').to_uppercase();
+            let mut constant = base.clone();
+            let mut count = 2;
+            while !used_consts.insert(constant.clone()) {
+                constant = format!("{}_{}", base, count);
+                count += 1;
+            }
+            write!(
+                code,
+                "\n    /// {} `{}`.\n    pub const {}: MemberName<'static> = \
+                 MemberName::from_static_str_unchecked({:?});\n",
+                kind, member, constant, member
+            )
+            .unwrap();
+        }
+        code.push_str("}\n");
+    }
+    code
+}
@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+
+thread_local! {
+    static COLLECTED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Reports a problem that does not stop generation. Printed to stderr, unless
+/// collected by [`collect_warnings`].
+pub fn warn(message: String) {
+    let unclaimed = COLLECTED.with(|collected| match collected.borrow_mut().as_mut() {
+        Some(warnings) => {
+            warnings.push(message);
+            None
+        }
+        None => Some(message),
+    });
+    if let Some(message) = unclaimed {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+/// Runs `f`, returning the warnings it reported instead of printing them.
+pub fn collect_warnings<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let outer = COLLECTED.with(|collected| collected.replace(Some(Vec::new())));
+    let result = f();
+    let warnings = COLLECTED.with(|collected| collected.replace(outer));
+    (result, warnings.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_warnings() {
+        let (value, warnings) = collect_warnings(|| {
+            warn("first".to_string());
+            warn("second".to_string());
+            42
+        });
+        assert_eq!(value, 42);
+        assert_eq!(warnings, ["first", "second"]);
+    }
+
+    #[test]
+    fn nested_collection_is_isolated() {
+        let (_, outer) = collect_warnings(|| {
+            warn("outer".to_string());
+            let (_, inner) = collect_warnings(|| warn("inner".to_string()));
+            assert_eq!(inner, ["inner"]);
+        });
+        assert_eq!(outer, ["outer"]);
+    }
+}
@@ -0,0 +1,398 @@
+use quick_xml::escape::escape;
+use zbus_xml::{Annotation, Arg, ArgDirection, Interface, Node, PropertyAccess};
+
+use crate::codegen::{dbus_type_to_rust, interfaces_by_path, unique_by_name, Options};
+
+/// The markup of generated API documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    /// A standalone HTML page.
+    Html,
+}
+
+/// API documentation for the interfaces: their methods, properties and
+/// signals with argument directions, D-Bus and Rust types, annotations and
+/// example `busctl` lines. `options` supplies type overrides, interface
+/// renames and the service and object path used in the examples.
+pub fn generate_docs_from_xml(xml: &str, format: DocFormat, options: &Options) -> String {
+    let node = Node::from_reader(std::io::Cursor::new(xml)).expect("Failed to parse D-Bus XML");
+    generate_docs(&interfaces_by_path(&node), format, options)
+}
+
+/// Documentation for interfaces registered at the given object paths.
+pub(crate) fn generate_docs(
+    registrations: &[(String, &Interface)],
+    format: DocFormat,
+    options: &Options,
+) -> String {
+    let interfaces = unique_by_name(registrations);
+    let options = &options.with_unique_names(&interfaces);
+    let mut doc = Doc::new(format);
+    for iface in interfaces {
+        let path = options.default_path.clone().unwrap_or_else(|| {
+            registrations
+                .iter()
+                .find(|(_, registered)| registered.name() == iface.name())
+                .map(|(path, _)| path.clone())
+                .unwrap_or_else(|| "/".to_string())
+        });
+        document_interface(&mut doc, iface, &path, options);
+    }
+    doc.finish()
+}
+
+fn document_interface(doc: &mut Doc, iface: &Interface, path: &str, options: &Options) {
+    let name = iface.name().to_string();
+    doc.heading(1, &doc.code(&name));
+    let rust_name = options.interface_rust_name(&name);
+    doc.paragraph(&format!(
+        "Rust: {} (client proxy {}), object path {}.",
+        doc.code(&rust_name),
+        doc.code(&format!("{}Proxy", rust_name)),
+        doc.code(path)
+    ));
+    doc.annotations(iface.annotations());
+    let service = options.default_service.as_deref().unwrap_or("SERVICE");
+    let target = format!("{} {} {}", service, path, name);
+
+    if !iface.methods().is_empty() {
+        doc.heading(2, "Methods");
+    }
+    for method in iface.methods() {
+        let member = method.name().to_string();
+        doc.heading(
+            3,
+            &doc.code(&format!("{}{}", member, args_signature(method.args()))),
+        );
+        doc.annotations(method.annotations());
+        doc.args(method.args(), true, options);
+        let in_args: Vec<&Arg> = method
+            .args()
+            .iter()
+            .filter(|arg| arg.direction() != Some(ArgDirection::Out))
+            .collect();
+        let mut call = format!("busctl call {} {}", target, member);
+        if !in_args.is_empty() {
+            let signature: String = in_args.iter().map(|arg| arg.ty().to_string()).collect();
+            call.push_str(&format!(" {}", shell_quote(&signature)));
+            for arg in in_args {
+                call.push(' ');
+                call.push_str(&example_value(&arg.ty().to_string(), arg.name()));
+            }
+        }
+        doc.code_block(&[call]);
+    }
+
+    if !iface.properties().is_empty() {
+        doc.heading(2, "Properties");
+    }
+    for prop in iface.properties() {
+        let member = prop.name().to_string();
+        let ty = prop.ty().to_string();
+        doc.heading(3, &doc.code(&member));
+        let access = match prop.access() {
+            PropertyAccess::Read => "read-only",
+            PropertyAccess::Write => "write-only",
+            PropertyAccess::ReadWrite => "read-write",
+        };
+        doc.paragraph(&format!(
+            "{} ({}), {}.",
+            doc.code(&ty),
+            doc.code(&dbus_type_to_rust(&ty, &options.type_overrides)),
+            access
+        ));
+        doc.annotations(prop.annotations());
+        let mut lines = Vec::new();
+        if prop.access().read() {
+            lines.push(format!("busctl get-property {} {}", target, member));
+        }
+        if prop.access().write() {
+            lines.push(format!(
+                "busctl set-property {} {} {} {}",
+                target,
+                member,
+                shell_quote(&ty),
+                example_value(&ty, Some(&member))
+            ));
+        }
+        doc.code_block(&lines);
+    }
+
+    if !iface.signals().is_empty() {
+        doc.heading(2, "Signals");
+    }
+    for signal in iface.signals() {
+        let member = signal.name().to_string();
+        doc.heading(
+            3,
+            &doc.code(&format!("{}{}", member, args_signature(signal.args()))),
+        );
+        doc.annotations(signal.annotations());
+        doc.args(signal.args(), false, options);
+        doc.code_block(&[format!(
+            "busctl monitor {} --match \"type='signal',interface='{}',member='{}'\"",
+            service, name, member
+        )]);
+    }
+}
+
+/// `(s key, u flags) -> (v value)`-style argument lists.
+fn args_signature(args: &[Arg]) -> String {
+    let list = |out: bool| {
+        let args: Vec<String> = args
+            .iter()
+            .filter(|arg| (arg.direction() == Some(ArgDirection::Out)) == out)
+            .map(|arg| match arg.name() {
+                Some(name) => format!("{} {}", arg.ty().to_string(), name),
+                None => arg.ty().to_string(),
+            })
+            .collect();
+        format!("({})", args.join(", "))
+    };
+    match list(true).as_str() {
+        "()" => list(false),
+        outputs => format!("{} -> {}", list(false), outputs),
+    }
+}
+
+/// Quotes signatures holding `(` or `{` for the shell.
+fn shell_quote(signature: &str) -> String {
+    if signature.contains(|c: char| !c.is_ascii_alphanumeric()) {
+        format!("'{}'", signature)
+    } else {
+        signature.to_string()
+    }
+}
+
+/// A `busctl` command-line value of D-Bus type `ty`.
+fn example_value(ty: &str, name: Option<&str>) -> String {
+    let mut chars = ty.chars().peekable();
+    let mut values = Vec::new();
+    while chars.peek().is_some() {
+        values.push(example_single(&mut chars, name));
+    }
+    values.join(" ")
+}
+
+fn example_single<I>(chars: &mut std::iter::Peekable<I>, name: Option<&str>) -> String
+where
+    I: Iterator<Item = char>,
+{
+    match chars.next() {
+        Some('b') => "false".to_string(),
+        Some('s') => format!("\"{}\"", name.unwrap_or("")),
+        Some('o') => "/".to_string(),
+        Some('g') => "\"\"".to_string(),
+        Some('v') => "s \"\"".to_string(),
+        Some('a') => {
+            // An empty array: busctl takes the element count first.
+            skip_single(chars);
+            "0".to_string()
+        }
+        Some('(') => {
+            let mut fields = Vec::new();
+            while chars.peek().is_some_and(|&c| c != ')') {
+                fields.push(example_single(chars, None));
+            }
+            chars.next();
+            fields.join(" ")
+        }
+        _ => "0".to_string(),
+    }
+}
+
+/// Skips one complete type.
+fn skip_single<I>(chars: &mut std::iter::Peekable<I>)
+where
+    I: Iterator<Item = char>,
+{
+    match chars.next() {
+        Some('a') => skip_single(chars),
+        Some(open @ '(') | Some(open @ '{') => {
+            let close = if open == '(' { ')' } else { '}' };
+            while chars.peek().is_some_and(|&c| c != close) {
+                skip_single(chars);
+            }
+            chars.next();
+        }
+        _ => {}
+    }
+}
+
+/// A document being written in one of the [`DocFormat`]s. Text passed in is
+/// markup already, built with [`Doc::code`].
+struct Doc {
+    format: DocFormat,
+    out: String,
+}
+
+impl Doc {
+    fn new(format: DocFormat) -> Self {
+        let out = match format {
+            DocFormat::Markdown => String::new(),
+            DocFormat::Html => "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                <title>D-Bus API</title>\n</head>\n<body>\n"
+                .to_string(),
+        };
+        Doc { format, out }
+    }
+
+    fn finish(mut self) -> String {
+        if self.format == DocFormat::Html {
+            self.out.push_str("</body>\n</html>\n");
+        }
+        self.out
+    }
+
+    /// Inline code. In Markdown, the fence is one backtick longer than the
+    /// longest run of them in `text`, and padded with spaces when `text`
+    /// starts or ends with a backtick or space.
+    fn code(&self, text: &str) -> String {
+        match self.format {
+            DocFormat::Markdown => {
+                let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+                let fence = "`".repeat(longest_run + 1);
+                let pad = if text.starts_with(['`', ' ']) || text.ends_with(['`', ' ']) {
+                    " "
+                } else {
+                    ""
+                };
+                format!("{0}{1}{2}{1}{0}", fence, pad, text)
+            }
+            DocFormat::Html => format!("<code>{}</code>", escape(text)),
+        }
+    }
+
+    fn heading(&mut self, level: usize, text: &str) {
+        let text = text.to_string();
+        match self.format {
+            DocFormat::Markdown => {
+                if !self.out.is_empty() {
+                    self.out.push('\n');
+                }
+                self.out
+                    .push_str(&format!("{} {}\n", "#".repeat(level), text));
+            }
+            DocFormat::Html => self
+                .out
+                .push_str(&format!("<h{0}>{1}</h{0}>\n", level, text)),
+        }
+    }
+
+    fn paragraph(&mut self, text: &str) {
+        match self.format {
+            DocFormat::Markdown => self.out.push_str(&format!("\n{}\n", text)),
+            DocFormat::Html => self.out.push_str(&format!("<p>{}</p>\n", text)),
+        }
+    }
+
+    fn list(&mut self, items: &[String]) {
+        match self.format {
+            DocFormat::Markdown => {
+                self.out.push('\n');
+                for item in items {
+                    self.out.push_str(&format!("- {}\n", item));
+                }
+            }
+            DocFormat::Html => {
+                self.out.push_str("<ul>\n");
+                for item in items {
+                    self.out.push_str(&format!("<li>{}</li>\n", item));
+                }
+                self.out.push_str("</ul>\n");
+            }
+        }
+    }
+
+    fn table(&mut self, header: &[&str], rows: &[Vec<String>]) {
+        match self.format {
+            DocFormat::Markdown => {
+                self.out
+                    .push_str(&format!("\n| {} |\n", header.join(" | ")));
+                self.out
+                    .push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+                for row in rows {
+                    let cells: Vec<_> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                    self.out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+            }
+            DocFormat::Html => {
+                self.out.push_str("<table>\n<tr>");
+                for cell in header {
+                    self.out.push_str(&format!("<th>{}</th>", cell));
+                }
+                self.out.push_str("</tr>\n");
+                for row in rows {
+                    self.out.push_str("<tr>");
+                    for cell in row {
+                        self.out.push_str(&format!("<td>{}</td>", cell));
+                    }
+                    self.out.push_str("</tr>\n");
+                }
+                self.out.push_str("</table>\n");
+            }
+        }
+    }
+
+    /// A block of shell commands.
+    fn code_block(&mut self, lines: &[String]) {
+        match self.format {
+            DocFormat::Markdown => {
+                self.out
+                    .push_str(&format!("\n```sh\n{}\n```\n", lines.join("\n")));
+            }
+            DocFormat::Html => {
+                let lines: Vec<_> = lines.iter().map(|line| escape(line.as_str())).collect();
+                self.out
+                    .push_str(&format!("<pre><code>{}</code></pre>\n", lines.join("\n")));
+            }
+        }
+    }
+
+    fn annotations(&mut self, annotations: &[Annotation]) {
+        if annotations.is_empty() {
+            return;
+        }
+        let items: Vec<String> = annotations
+            .iter()
+            .map(|a| format!("{} = {}", self.code(a.name()), self.code(a.value())))
+            .collect();
+        self.list(&items);
+    }
+
+    /// A table of arguments, with a direction column for methods.
+    fn args(&mut self, args: &[Arg], directions: bool, options: &Options) {
+        if args.is_empty() {
+            return;
+        }
+        let mut header = vec!["Argument"];
+        if directions {
+            header.push("Direction");
+        }
+        header.extend(vec!["D-Bus type", "Rust type"]);
+        let rows: Vec<Vec<String>> = args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                let ty = arg.ty().to_string();
+                let name = match arg.name() {
+                    Some(name) => name.to_string(),
+                    None => format!("arg{}", i),
+                };
+                let mut row = vec![self.code(&name)];
+                if directions {
+                    let direction = match arg.direction() {
+                        Some(ArgDirection::Out) => "out",
+                        _ => "in",
+                    };
+                    row.push(direction.to_string());
+                }
+                row.push(self.code(&ty));
+                row.push(self.code(&dbus_type_to_rust(&ty, &options.type_overrides)));
+                row
+            })
+            .collect();
+        self.table(&header, &rows);
+    }
+}
@@ -0,0 +1,202 @@
+use syn::{File, Item, Visibility};
+
+/// Parses generated code as a Rust file and pretty-prints it, so anything
+/// that is not valid Rust surfaces as an error instead of a broken file.
+pub fn format_code(code: &str) -> Result<String, syn::Error> {
+    let file = syn::parse_file(code)?;
+    let mut out = String::new();
+    if !file.attrs.is_empty() {
+        let attrs = unparse(File {
+            items: Vec::new(),
+            ..file.clone()
+        });
+        out.push_str(attrs.trim_end());
+        out.push_str(if file.items.is_empty() { "\n" } else { "\n\n" });
+    }
+    out.push_str(&join_items(
+        file.items
+            .iter()
+            .map(|item| (print_item(item), group(item))),
+    ));
+    Ok(out)
+}
+
+/// Unwraps code generated by the `*_from_xml` functions, which panic on
+/// invalid input.
+pub(crate) fn expect_valid<T>(code: Result<T, syn::Error>) -> T {
+    code.unwrap_or_else(|err| panic!("Generated code is not valid Rust: {}", err))
+}
+
+/// Kinds of items kept together without blank lines between them.
+#[derive(Clone, Copy, PartialEq)]
+enum Group {
+    Imports,
+    Modules,
+    Other,
+}
+
+fn group(item: &Item) -> Group {
+    match item {
+        Item::Use(item) if matches!(item.vis, Visibility::Inherited) => Group::Imports,
+        Item::Use(_) | Item::Mod(syn::ItemMod { content: None, .. }) => Group::Modules,
+        _ => Group::Other,
+    }
+}
+
+/// Joins printed items, as `prettyplease` would print them together, with
+/// a blank line after each item ending in a block and after each group of
+/// imports or module declarations, as rustfmt-formatted code has them.
+fn join_items(items: impl Iterator<Item = (String, Group)>) -> String {
+    let mut out = String::new();
+    let mut prev: Option<(bool, Group)> = None;
+    for (text, group) in items {
+        if let Some((ends_in_block, prev_group)) = prev {
+            if ends_in_block || (prev_group != Group::Other && prev_group != group) {
+                out.push('\n');
+            }
+        }
+        let ends_in_block = text.trim_end().ends_with('}');
+        out.push_str(&text);
+        prev = Some((ends_in_block, group));
+    }
+    out
+}
+
+/// An item printed on its own, with the items of inline modules, traits and
+/// impls separated like top-level items.
+fn print_item(item: &Item) -> String {
+    match item {
+        Item::Mod(module) if module.content.is_some() => {
+            let (brace, items) = module.content.clone().unwrap();
+            let header = syn::ItemMod {
+                content: Some((brace, Vec::new())),
+                ..module.clone()
+            };
+            let body = join_items(items.iter().map(|item| (print_item(item), group(item))));
+            with_body(Item::Mod(header), &indent(&body))
+        }
+        Item::Trait(item_trait) => {
+            let header = syn::ItemTrait {
+                items: Vec::new(),
+                ..item_trait.clone()
+            };
+            let body = join_items(item_trait.items.iter().map(|trait_item| {
+                let mut wrapper: syn::ItemTrait = syn::parse_quote!(
+                    trait __ {}
+                );
+                wrapper.items.push(trait_item.clone());
+                (unwrap_body(Item::Trait(wrapper)), Group::Other)
+            }));
+            with_body(Item::Trait(header), &body)
+        }
+        Item::Impl(item_impl) => {
+            let header = syn::ItemImpl {
+                items: Vec::new(),
+                ..item_impl.clone()
+            };
+            let body = join_items(item_impl.items.iter().map(|impl_item| {
+                let mut wrapper: syn::ItemImpl = syn::parse_quote!(impl __ {});
+                wrapper.items.push(impl_item.clone());
+                (unwrap_body(Item::Impl(wrapper)), Group::Other)
+            }));
+            with_body(Item::Impl(header), &body)
+        }
+        _ => unparse_item(item.clone()),
+    }
+}
+
+fn unparse(file: File) -> String {
+    prettyplease::unparse(&file)
+}
+
+fn unparse_item(item: Item) -> String {
+    unparse(File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![item],
+    })
+}
+
+/// `item`, printed with an empty body, with `body` in its braces instead.
+fn with_body(item: Item, body: &str) -> String {
+    let empty = unparse_item(item);
+    match empty.strip_suffix("{}\n") {
+        Some(header) if !body.is_empty() => format!("{}{{\n{}}}\n", header, body),
+        _ => empty,
+    }
+}
+
+/// The indented body of a one-item trait or impl wrapper.
+fn unwrap_body(wrapper: Item) -> String {
+    let printed = unparse_item(wrapper);
+    let mut lines: Vec<_> = printed.lines().skip(1).collect();
+    lines.pop();
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| {
+            if line.is_empty() {
+                "\n".to_string()
+            } else {
+                format!("    {}\n", line)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_valid_code() {
+        let code = "use zbus::Result;\npub trait Foo {\n  fn bar(&self)->u32;\n}\npub struct Bar;";
+        assert_eq!(
+            format_code(code).unwrap(),
+            "use zbus::Result;\n\npub trait Foo {\n    fn bar(&self) -> u32;\n}\n\npub struct Bar;\n"
+        );
+    }
+
+    #[test]
+    fn separates_methods() {
+        let code = "impl Foo { fn a(&self) {} fn b(&self) { x(); } fn c(&self) { y(); } }";
+        assert_eq!(
+            format_code(code).unwrap(),
+            "impl Foo {\n    fn a(&self) {}\n\n    fn b(&self) {\n        x();\n    }\n\n    fn c(&self) {\n        y();\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_fn_bodies_together() {
+        let code = "fn f() { if a { b(); } c(); match x { _ => { d(); } } }";
+        assert_eq!(
+            format_code(code).unwrap(),
+            "fn f() {\n    if a {\n        b();\n    }\n    c();\n    match x {\n        _ => {\n            d();\n        }\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn separates_items_in_modules() {
+        let code = "#![allow(dead_code)] pub mod a { use super::*; pub struct B { x: u8 } pub type C = u8; } mod d; pub use d::*;";
+        assert_eq!(
+            format_code(code).unwrap(),
+            "#![allow(dead_code)]\n\npub mod a {\n    use super::*;\n\n    pub struct B {\n        x: u8,\n    }\n\n    pub type C = u8;\n}\n\nmod d;\npub use d::*;\n"
+        );
+    }
+
+    #[test]
+    fn formats_bodiless_impl_fns() {
+        let code = "impl Foo { #[zbus(signal)] async fn changed(e: &E, x: u32) -> Result<()>; fn a(&self) {} }";
+        assert_eq!(
+            format_code(code).unwrap(),
+            "impl Foo {\n    #[zbus(signal)]\n    async fn changed(e: &E, x: u32) -> Result<()>;\n    fn a(&self) {}\n}\n"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_code() {
+        assert!(format_code("pub trait Foo { fn match(&self); }").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt::Write;
+
+use crate::codegen::{Dispatch, Mode, Options};
+
+/// Comment block recording how a generated file was produced: the
+/// `@generated` marker, generator version, source paths, a hash of their
+/// contents and the options. `sources` are `(path, xml)` pairs.
+///
+/// Formatting drops comments, so add it to code after [`format_code`].
+///
+/// [`format_code`]: crate::format_code
+pub fn generate_header(sources: &[(&str, &str)], mode: Mode, options: &Options) -> String {
+    let paths: Vec<_> = sources.iter().map(|(path, _)| *path).collect();
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// @generated by zbus-xml-gen {}. Do not edit.",
+        env!("CARGO_PKG_VERSION")
+    )
+    .unwrap();
+    writeln!(out, "//").unwrap();
+    writeln!(out, "// Sources: {}", paths.join(", ")).unwrap();
+    writeln!(out, "// Input hash: fnv1a64:{:016x}", input_hash(sources)).unwrap();
+    writeln!(out, "// Options: {}", describe_options(mode, options)).unwrap();
+    out.push('\n');
+
+    out
+}
+
+/// `#![allow(...)]` for `Options::allow`, if any.
+pub(super) fn generate_allow_attributes(options: &Options) -> String {
+    if options.allow.is_empty() {
+        return String::new();
+    }
+    format!("#![allow({})]\n\n", options.allow.join(", "))
+}
+
+/// FNV-1a over the source contents, stable across platforms and releases.
+fn input_hash(sources: &[(&str, &str)]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for (_, xml) in sources {
+        // 0xff never occurs in UTF-8, so it separates sources unambiguously.
+        for byte in xml.bytes().chain(std::iter::once(0xff)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// The options as CLI flags, without the leading `--`. Options keyed by
+/// name read `option[key]=value`, in key order.
+fn describe_options(mode: Mode, options: &Options) -> String {
+    let mut flags = vec![mode_name(mode).to_string()];
+    if mode != Mode::Client {
+        let toggles = [
+            (options.dispatch == Dispatch::Static, "static-dispatch"),
+            (options.signals, "signals"),
+            (options.notifier, "notifier"),
+            (options.serve, "serve"),
+            (options.mock, "mock"),
+            (options.default_bodies, "default-bodies"),
+        ];
+        for (enabled, flag) in toggles.iter() {
+            if *enabled {
+                flags.push(flag.to_string());
+            }
+        }
+        if !options.context.is_empty() {
+            flags.push(format!("context={}", options.context));
+        }
+        for (method, params) in &options.method_context {
+            flags.push(format!("context[{}]={}", method, params));
+        }
+    }
+    if options.names {
+        flags.push("names".to_string());
+    }
+    for lint in &options.allow {
+        flags.push(format!("allow={}", lint));
+    }
+    for (signature, ty) in &options.type_overrides {
+        flags.push(format!("type[{}]={}", signature, ty));
+    }
+    for (interface, rename) in &options.renames {
+        flags.push(format!("rename[{}]={}", interface, rename));
+    }
+    for (interface, mode) in &options.interface_modes {
+        flags.push(format!("mode[{}]={}", interface, mode_name(*mode)));
+    }
+    if let Some(service) = &options.default_service {
+        flags.push(format!("service={}", service));
+    }
+    if let Some(path) = &options.default_path {
+        flags.push(format!("path={}", path));
+    }
+    flags.join(" ")
+}
+
+fn mode_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Client => "client",
+        Mode::Server => "server",
+        Mode::Both => "both",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::ContextParams;
+
+    #[test]
+    fn header() {
+        let options = Options {
+            signals: true,
+            context: ContextParams {
+                header: true,
+                ..ContextParams::default()
+            },
+            allow: vec!["dead_code".to_string()],
+            ..Options::default()
+        };
+        let header = generate_header(&[("a.xml", "<node/>")], Mode::Server, &options);
+        assert!(header.starts_with("// @generated by zbus-xml-gen "));
+        assert!(header.contains("// Sources: a.xml\n"));
+        assert!(header.contains("// Options: server signals context=header allow=dead_code\n"));
+    }
+
+    #[test]
+    fn header_lists_keyed_options_in_order() {
+        let mut options = Options {
+            default_service: Some("org.example.Service".to_string()),
+            default_path: Some("/org/example".to_string()),
+            ..Options::default()
+        };
+        options
+            .type_overrides
+            .insert("a{sv}".to_string(), "crate::Hints".to_string());
+        options
+            .type_overrides
+            .insert("a(ii)".to_string(), "crate::Points".to_string());
+        options
+            .renames
+            .insert("org.example.Foo".to_string(), "Bar".to_string());
+        options
+            .interface_modes
+            .insert("org.example.Foo".to_string(), Mode::Both);
+        assert_eq!(
+            describe_options(Mode::Client, &options),
+            "client type[a(ii)]=crate::Points type[a{sv}]=crate::Hints \
+             rename[org.example.Foo]=Bar mode[org.example.Foo]=both \
+             service=org.example.Service path=/org/example"
+        );
+    }
+
+    #[test]
+    fn input_hash_depends_on_contents() {
+        let hash = input_hash(&[("a.xml", "<node/>")]);
+        assert_eq!(hash, input_hash(&[("b.xml", "<node/>")]));
+        assert_ne!(hash, input_hash(&[("a.xml", "<node></node>")]));
+        assert_ne!(
+            input_hash(&[("a.xml", "ab"), ("b.xml", "c")]),
+            input_hash(&[("a.xml", "a"), ("b.xml", "bc")])
+        );
+    }
+}
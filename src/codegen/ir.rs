@@ -0,0 +1,324 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+use zbus_xml::{Annotation, Arg, ArgDirection, Interface, Node, PropertyAccess};
+
+use crate::codegen::{
+    dbus_type_to_rust, interfaces_by_path, proxy_member_name, rust_ident, unique_by_name, Options,
+};
+
+/// Version of the [`Ir`] JSON schema. It changes when a field is removed or
+/// changes meaning; added fields keep the version.
+pub const IR_SCHEMA_VERSION: u32 = 1;
+
+/// The interfaces as the generator sees them: D-Bus names next to the Rust
+/// names and types generated for them, for tools that work alongside
+/// generated code. Serializes to the JSON of `--emit ir-json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Ir {
+    /// [`IR_SCHEMA_VERSION`].
+    pub schema_version: u32,
+    pub interfaces: Vec<IrInterface>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IrInterface {
+    /// The D-Bus name, e.g. `org.example.Foo`.
+    pub name: String,
+    /// The trait name, e.g. `Foo`, after renames.
+    pub rust_name: String,
+    /// The client proxy type, e.g. `FooProxy`.
+    pub proxy_name: String,
+    /// Object paths the interface is at.
+    pub paths: Vec<String>,
+    pub annotations: Vec<IrAnnotation>,
+    pub methods: Vec<IrMethod>,
+    pub properties: Vec<IrProperty>,
+    pub signals: Vec<IrSignal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IrMethod {
+    pub name: String,
+    /// The proxy method name.
+    pub rust_name: String,
+    pub args: Vec<IrArg>,
+    pub annotations: Vec<IrAnnotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IrProperty {
+    pub name: String,
+    /// The proxy getter name.
+    pub rust_name: String,
+    /// The proxy setter name, if the property is writable.
+    pub rust_setter: Option<String>,
+    /// `read`, `write` or `readwrite`.
+    pub access: &'static str,
+    #[serde(rename = "type")]
+    pub ty: IrType,
+    pub rust_type: String,
+    pub annotations: Vec<IrAnnotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IrSignal {
+    pub name: String,
+    /// The proxy method returning the signal stream.
+    pub rust_name: String,
+    pub args: Vec<IrArg>,
+    pub annotations: Vec<IrAnnotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IrArg {
+    pub name: Option<String>,
+    /// The parameter name, unique among the arguments of the same direction.
+    pub rust_name: String,
+    /// `in` or `out`; signal arguments are `out`.
+    pub direction: &'static str,
+    #[serde(rename = "type")]
+    pub ty: IrType,
+    pub rust_type: String,
+    pub annotations: Vec<IrAnnotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IrAnnotation {
+    pub name: String,
+    pub value: String,
+}
+
+/// A parsed D-Bus type. Every node carries its complete signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IrType {
+    /// `y`, `b`, `n`, `q`, `i`, `u`, `x`, `t`, `d`, `h`, `s`, `o` or `g`.
+    Basic {
+        signature: String,
+    },
+    Variant {
+        signature: String,
+    },
+    Array {
+        signature: String,
+        element: Box<IrType>,
+    },
+    Dict {
+        signature: String,
+        key: Box<IrType>,
+        value: Box<IrType>,
+    },
+    Struct {
+        signature: String,
+        fields: Vec<IrType>,
+    },
+}
+
+impl IrType {
+    /// Parses a single complete type.
+    pub fn parse(signature: &str) -> IrType {
+        let (ty, _) = parse_type(signature);
+        ty
+    }
+}
+
+/// Parses the complete type at the start of `signature`, returning it and
+/// the rest.
+fn parse_type(signature: &str) -> (IrType, &str) {
+    let mut chars = signature.chars();
+    let first = chars.next().unwrap_or('v');
+    let rest = chars.as_str();
+    match first {
+        'a' if rest.starts_with('{') => {
+            let (key, after_key) = parse_type(&rest[1..]);
+            let (value, after_value) = parse_type(after_key);
+            let after = after_value.strip_prefix('}').unwrap_or(after_value);
+            let ty = IrType::Dict {
+                signature: consumed(signature, after),
+                key: Box::new(key),
+                value: Box::new(value),
+            };
+            (ty, after)
+        }
+        'a' => {
+            let (element, after) = parse_type(rest);
+            let ty = IrType::Array {
+                signature: consumed(signature, after),
+                element: Box::new(element),
+            };
+            (ty, after)
+        }
+        '(' => {
+            let mut fields = Vec::new();
+            let mut after = rest;
+            while !after.is_empty() && !after.starts_with(')') {
+                let (field, next) = parse_type(after);
+                fields.push(field);
+                after = next;
+            }
+            let after = after.strip_prefix(')').unwrap_or(after);
+            let ty = IrType::Struct {
+                signature: consumed(signature, after),
+                fields,
+            };
+            (ty, after)
+        }
+        'v' => (
+            IrType::Variant {
+                signature: "v".to_string(),
+            },
+            rest,
+        ),
+        _ => (
+            IrType::Basic {
+                signature: first.to_string(),
+            },
+            rest,
+        ),
+    }
+}
+
+/// The part of `signature` before its suffix `rest`.
+fn consumed(signature: &str, rest: &str) -> String {
+    signature[..signature.len() - rest.len()].to_string()
+}
+
+impl Ir {
+    /// Pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("IR serializes to JSON");
+        json.push('\n');
+        json
+    }
+}
+
+/// The IR of the interfaces of `xml`.
+pub fn ir_from_xml(xml: &str, options: &Options) -> Ir {
+    let node = Node::from_reader(std::io::Cursor::new(xml)).expect("Failed to parse D-Bus XML");
+    build_ir(&interfaces_by_path(&node), options)
+}
+
+/// The IR of interfaces registered at the given object paths.
+pub(crate) fn build_ir(registrations: &[(String, &Interface)], options: &Options) -> Ir {
+    let interfaces = unique_by_name(registrations);
+    let options = &options.with_unique_names(&interfaces);
+    let interfaces = interfaces
+        .iter()
+        .map(|iface| {
+            let paths = registrations
+                .iter()
+                .filter(|(_, other)| other.name() == iface.name())
+                .map(|(path, _)| path.clone())
+                .collect();
+            interface_ir(iface, paths, options)
+        })
+        .collect();
+    Ir {
+        schema_version: IR_SCHEMA_VERSION,
+        interfaces,
+    }
+}
+
+fn interface_ir(iface: &Interface, paths: Vec<String>, options: &Options) -> IrInterface {
+    let rust_name = options.interface_rust_name(&iface.name());
+    // Named in the order the client proxy names them.
+    let mut used_names = HashSet::new();
+    let methods = iface
+        .methods()
+        .iter()
+        .map(|method| IrMethod {
+            name: method.name().to_string(),
+            rust_name: proxy_member_name(&method.name(), &mut used_names, false),
+            args: args_ir(method.args(), options),
+            annotations: annotations_ir(method.annotations()),
+        })
+        .collect();
+    let properties = iface
+        .properties()
+        .iter()
+        .map(|prop| {
+            let ty = prop.ty().to_string();
+            let rust_name = proxy_member_name(&prop.name(), &mut used_names, true);
+            IrProperty {
+                name: prop.name().to_string(),
+                rust_setter: prop.access().write().then(|| format!("set_{}", rust_name)),
+                rust_name,
+                access: match prop.access() {
+                    PropertyAccess::Read => "read",
+                    PropertyAccess::Write => "write",
+                    PropertyAccess::ReadWrite => "readwrite",
+                },
+                rust_type: dbus_type_to_rust(&ty, &options.type_overrides),
+                ty: IrType::parse(&ty),
+                annotations: annotations_ir(prop.annotations()),
+            }
+        })
+        .collect();
+    let signals = iface
+        .signals()
+        .iter()
+        .map(|signal| IrSignal {
+            name: signal.name().to_string(),
+            rust_name: proxy_member_name(&signal.name(), &mut used_names, false),
+            args: args_ir(signal.args(), options),
+            annotations: annotations_ir(signal.annotations()),
+        })
+        .collect();
+
+    IrInterface {
+        name: iface.name().to_string(),
+        proxy_name: format!("{}Proxy", rust_name),
+        rust_name,
+        paths,
+        annotations: annotations_ir(iface.annotations()),
+        methods,
+        properties,
+        signals,
+    }
+}
+
+fn is_in(arg: &Arg) -> bool {
+    arg.direction() == Some(ArgDirection::In)
+}
+
+/// Arguments in order, with parameter names deduplicated among those of the
+/// same direction like the client proxy does.
+fn args_ir(args: &[Arg], options: &Options) -> Vec<IrArg> {
+    let (mut used_in, mut used_out) = (HashSet::new(), HashSet::new());
+    args.iter()
+        .map(|arg| {
+            let used = if is_in(arg) {
+                &mut used_in
+            } else {
+                &mut used_out
+            };
+            let base = rust_ident(arg.name().unwrap_or("arg"));
+            let mut rust_name = base.clone();
+            let mut count = 2;
+            while !used.insert(rust_name.clone()) {
+                rust_name = format!("{}_{}", base, count);
+                count += 1;
+            }
+            let ty = arg.ty().to_string();
+            IrArg {
+                name: arg.name().map(str::to_string),
+                rust_name,
+                direction: if is_in(arg) { "in" } else { "out" },
+                rust_type: dbus_type_to_rust(&ty, &options.type_overrides),
+                ty: IrType::parse(&ty),
+                annotations: annotations_ir(arg.annotations()),
+            }
+        })
+        .collect()
+}
+
+fn annotations_ir(annotations: &[Annotation]) -> Vec<IrAnnotation> {
+    annotations
+        .iter()
+        .map(|annotation| IrAnnotation {
+            name: annotation.name().to_string(),
+            value: annotation.value().to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,253 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use zbus_xml::Interface;
+
+use crate::codegen::server::{
+    method_context, method_in_args, method_return_type, property_getter_type, ServerNames,
+};
+use crate::codegen::{dbus_type_to_rust, rust_ident, to_pascal_case};
+use crate::codegen::{Dispatch, Options};
+
+/// One delegate trait method as seen by the mock.
+struct MockMember {
+    fn_name: String,
+    variant: String,
+    args: Vec<(String, String)>,
+    ret: String,
+    /// Whether the trait method takes a `FooContext` first.
+    has_context: bool,
+}
+
+/// `MockFooDelegate`: answers each delegate method from a closure set with
+/// `expect_<method>()` and records every call as a `FooDelegateCall`.
+///
+/// Arguments whose type borrows (e.g. `Value<'_>`) are recorded as their
+/// `Debug` representation.
+pub fn generate_mock_delegate(
+    interface: &Interface,
+    names: &ServerNames,
+    options: &Options,
+) -> String {
+    let mock_name = format!("Mock{}", names.trait_name);
+    let call_name = format!("{}Call", names.trait_name);
+    let members = mock_members(interface, options);
+    let mut out = String::new();
+
+    // Recorded calls
+    writeln!(out, "/// A call recorded by [`{}`].", mock_name).unwrap();
+    writeln!(out, "#[derive(Debug, Clone, PartialEq)]").unwrap();
+    writeln!(out, "pub enum {} {{", call_name).unwrap();
+    for member in &members {
+        if member.args.is_empty() {
+            writeln!(out, "    {},", member.variant).unwrap();
+        } else {
+            let fields: Vec<_> = member
+                .args
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", name, recorded_type(ty)))
+                .collect();
+            writeln!(out, "    {} {{ {} }},", member.variant, fields.join(", ")).unwrap();
+        }
+    }
+    writeln!(out, "}}\n").unwrap();
+
+    // Mock struct
+    writeln!(
+        out,
+        "/// A `{}` answering calls from closures set with `expect_*()` and recording them.",
+        names.trait_name
+    )
+    .unwrap();
+    writeln!(out, "#[derive(Default)]").unwrap();
+    writeln!(out, "pub struct {} {{", mock_name).unwrap();
+    writeln!(out, "    calls: std::sync::Mutex<Vec<{}>>,", call_name).unwrap();
+    for member in &members {
+        writeln!(
+            out,
+            "    {}: Option<Box<dyn {} + Send + Sync>>,",
+            member.fn_name,
+            closure_type(member)
+        )
+        .unwrap();
+    }
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(out, "impl {} {{", mock_name).unwrap();
+    writeln!(out, "    pub fn new() -> Self {{").unwrap();
+    writeln!(out, "        Self::default()").unwrap();
+    writeln!(out, "    }}\n").unwrap();
+    writeln!(out, "    /// The calls received so far, in order.").unwrap();
+    writeln!(out, "    pub fn calls(&self) -> Vec<{}> {{", call_name).unwrap();
+    writeln!(out, "        self.calls.lock().unwrap().clone()").unwrap();
+    writeln!(out, "    }}").unwrap();
+    for member in &members {
+        writeln!(out).unwrap();
+        writeln!(out, "    /// Answers `{}` calls with `f`.", member.fn_name).unwrap();
+        writeln!(
+            out,
+            "    pub fn expect_{}(mut self, f: impl {} + Send + Sync + 'static) -> Self {{",
+            member.fn_name.trim_end_matches('_'),
+            closure_type(member)
+        )
+        .unwrap();
+        writeln!(out, "        self.{} = Some(Box::new(f));", member.fn_name).unwrap();
+        writeln!(out, "        self").unwrap();
+        writeln!(out, "    }}").unwrap();
+    }
+    writeln!(out, "}}\n").unwrap();
+
+    // Trait impl
+    if options.dispatch == Dispatch::Dynamic {
+        writeln!(out, "#[async_trait]").unwrap();
+    }
+    writeln!(out, "impl {} for {} {{", names.trait_name, mock_name).unwrap();
+    for (idx, member) in members.iter().enumerate() {
+        if idx > 0 {
+            writeln!(out).unwrap();
+        }
+        write!(
+            out,
+            "{}",
+            mock_method(member, names, &call_name, &mock_name)
+        )
+        .unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+fn mock_members(interface: &Interface, options: &Options) -> Vec<MockMember> {
+    let mut members = Vec::new();
+
+    for method in interface.methods() {
+        let fn_name = rust_ident(&method.name());
+        members.push(MockMember {
+            variant: to_pascal_case(&fn_name),
+            fn_name,
+            args: method_in_args(method, options),
+            ret: method_return_type(method, options),
+            has_context: !method_context(interface, method, options).is_empty(),
+        });
+    }
+    for prop in interface.properties() {
+        let name = rust_ident(&prop.name());
+        let ty = dbus_type_to_rust(&prop.ty().to_string(), &options.type_overrides);
+        if prop.access().read() {
+            members.push(MockMember {
+                variant: to_pascal_case(&name),
+                fn_name: name.clone(),
+                args: Vec::new(),
+                ret: property_getter_type(&ty, options),
+                has_context: false,
+            });
+        }
+        if prop.access().write() {
+            let fn_name = format!("set_{}", name);
+            members.push(MockMember {
+                variant: to_pascal_case(&fn_name),
+                fn_name,
+                args: vec![("val".to_string(), ty)],
+                ret: "Result<()>".to_string(),
+                has_context: false,
+            });
+        }
+    }
+
+    let mut used = HashSet::new();
+    for member in &mut members {
+        let base = member.variant.clone();
+        let mut idx = 2;
+        while !used.insert(member.variant.clone()) {
+            member.variant = format!("{}{}", base, idx);
+            idx += 1;
+        }
+    }
+
+    members
+}
+
+/// Borrowing types cannot outlive the call, so they are recorded as `Debug` output.
+fn is_borrowed(ty: &str) -> bool {
+    ty.contains("'_")
+}
+
+fn is_copy(ty: &str) -> bool {
+    matches!(
+        ty,
+        "u8" | "bool" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" | "f64"
+    )
+}
+
+fn recorded_type(ty: &str) -> &str {
+    if is_borrowed(ty) {
+        "String"
+    } else {
+        ty
+    }
+}
+
+fn closure_type(member: &MockMember) -> String {
+    let args: Vec<_> = member.args.iter().map(|(_, ty)| ty.as_str()).collect();
+    format!("Fn({}) -> {}", args.join(", "), member.ret)
+}
+
+fn mock_method(
+    member: &MockMember,
+    names: &ServerNames,
+    call_name: &str,
+    mock_name: &str,
+) -> String {
+    let mut params = String::new();
+    if member.has_context {
+        write!(params, ", _ctx: {}<'_>", names.context_name).unwrap();
+    }
+    for (name, ty) in &member.args {
+        write!(params, ", {}: {}", name, ty).unwrap();
+    }
+
+    let call = if member.args.is_empty() {
+        format!("{}::{}", call_name, member.variant)
+    } else {
+        let fields: Vec<_> = member
+            .args
+            .iter()
+            .map(|(name, ty)| {
+                if is_borrowed(ty) {
+                    format!("{}: format!(\"{{:?}}\", {})", name, name)
+                } else if is_copy(ty) {
+                    name.clone()
+                } else {
+                    format!("{}: {}.clone()", name, name)
+                }
+            })
+            .collect();
+        format!(
+            "{}::{} {{ {} }}",
+            call_name,
+            member.variant,
+            fields.join(", ")
+        )
+    };
+    let arg_names: Vec<_> = member.args.iter().map(|(name, _)| name.as_str()).collect();
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "    async fn {}(&self{}) -> {} {{",
+        member.fn_name, params, member.ret
+    )
+    .unwrap();
+    writeln!(out, "        self.calls.lock().unwrap().push({});", call).unwrap();
+    writeln!(out, "        match &self.{} {{", member.fn_name).unwrap();
+    writeln!(out, "            Some(f) => f({}),", arg_names.join(", ")).unwrap();
+    writeln!(
+        out,
+        "            None => panic!(\"unexpected call to {}::{}\"),",
+        mock_name, member.fn_name
+    )
+    .unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    out
+}
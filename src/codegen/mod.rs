@@ -1,8 +1,34 @@
+pub use self::cli_client::*;
 pub use self::client::*;
-use self::names::*;
+pub use self::combined::*;
+use self::constants::*;
+pub(crate) use self::diagnostics::*;
+pub use self::docs::*;
+pub use self::format::*;
+pub use self::header::*;
+#[cfg(feature = "json")]
+pub use self::ir::*;
+use self::mock::*;
+pub use self::modules::*;
+pub(crate) use self::names::*;
+pub(crate) use self::nodes::*;
+pub use self::options::*;
 pub use self::server::*;
 use self::types::*;
+mod cli_client;
 mod client;
+mod combined;
+mod constants;
+mod diagnostics;
+mod docs;
+mod format;
+mod header;
+#[cfg(feature = "json")]
+mod ir;
+mod mock;
+mod modules;
 mod names;
+mod nodes;
+mod options;
 mod server;
 mod types;
@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use zbus_xml::{Interface, Node};
+
+use crate::codegen::{
+    check_method_context, effective_mode, format_code, generate_allow_attributes,
+    generate_client_imports, generate_client_proxy, generate_interface_block,
+    generate_name_constants, generate_serve, generate_server_imports, interfaces_by_path,
+    rust_ident, shared_types, side_registrations, unique_by_name, Mode, Options,
+};
+use crate::Error;
+
+/// One generated Rust source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFile {
+    /// File name, e.g. `org_example_foo.rs`, or `client/mod.rs` for
+    /// [`Mode::Both`].
+    pub name: String,
+    pub code: String,
+}
+
+impl GeneratedFile {
+    /// Writes the file into `dir`, creating directories as needed, unless it
+    /// already holds the same code, so unchanged files keep their
+    /// modification time. Returns whether it was written.
+    pub fn write_to(&self, dir: &Path) -> io::Result<bool> {
+        let path = dir.join(&self.name);
+        if fs::read_to_string(&path).ok().as_deref() == Some(self.code.as_str()) {
+            return Ok(false);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &self.code)?;
+        Ok(true)
+    }
+}
+
+/// Code for the given side of the interfaces as one module per interface,
+/// plus a `mod.rs` holding the imports and re-exporting them. With
+/// [`Mode::Both`], `mod.rs` declares a `types` module and `client` and
+/// `server` directories laid out this way.
+pub fn generate_modules_from_xml(xml: &str, mode: Mode, options: &Options) -> Vec<GeneratedFile> {
+    let node = Node::from_reader(std::io::Cursor::new(xml)).expect("Failed to parse D-Bus XML");
+    generate_modules(&interfaces_by_path(&node), mode, options)
+        .unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Formatted modules for interfaces registered at the given object paths.
+/// Files that are not valid Rust are reported by name.
+pub(crate) fn generate_modules(
+    registrations: &[(String, &Interface)],
+    mode: Mode,
+    options: &Options,
+) -> Result<Vec<GeneratedFile>, Error> {
+    let options = &options.with_unique_names(&unique_by_name(registrations));
+    let mut files = match effective_mode(registrations, mode, options) {
+        Mode::Client => side_modules(registrations, false, options),
+        Mode::Server => side_modules(registrations, true, options),
+        Mode::Both => {
+            let (side_options, types) = shared_types(&unique_by_name(registrations), options);
+            let prelude = if types.is_empty() {
+                ""
+            } else {
+                "use super::types::*;\n"
+            };
+            let mut files = vec![
+                GeneratedFile {
+                    name: "mod.rs".to_string(),
+                    code: "pub mod types;\npub mod client;\npub mod server;\n".to_string(),
+                },
+                GeneratedFile {
+                    name: "types.rs".to_string(),
+                    code: types,
+                },
+            ];
+            let sides = [("client", Mode::Client), ("server", Mode::Server)];
+            for (dir, side) in sides.iter() {
+                let side_regs = side_registrations(registrations, *side, mode, options);
+                for mut file in side_modules(&side_regs, *side == Mode::Server, &side_options) {
+                    if file.name == "mod.rs" {
+                        file.code = format!("{}{}", prelude, file.code);
+                    }
+                    file.name = format!("{}/{}", dir, file.name);
+                    files.push(file);
+                }
+            }
+            files
+        }
+    };
+
+    if options.names {
+        files[0].code.push_str("\npub mod names;\n");
+        files.push(GeneratedFile {
+            name: "names.rs".to_string(),
+            code: generate_name_constants(&unique_by_name(registrations), options),
+        });
+    }
+
+    let allow = generate_allow_attributes(options);
+    for file in &mut files {
+        let code = format!("{}{}", allow, file.code);
+        file.code = format_code(&code).map_err(|err| Error::InvalidCode {
+            path: PathBuf::from(&file.name),
+            message: err.to_string(),
+        })?;
+    }
+    Ok(files)
+}
+
+fn side_modules(
+    registrations: &[(String, &Interface)],
+    server: bool,
+    options: &Options,
+) -> Vec<GeneratedFile> {
+    let interfaces = unique_by_name(registrations);
+    let mut root = if server {
+        check_method_context(&interfaces, options);
+        generate_server_imports(options)
+    } else {
+        generate_client_imports()
+    };
+
+    let mut files = Vec::new();
+    let mut used_names = HashSet::new();
+    for iface in interfaces {
+        let mut module = rust_ident(iface.name().as_str());
+        if !used_names.insert(module.clone()) {
+            let base = module.clone();
+            let mut idx = 2;
+            while !used_names.insert(format!("{}_{}", base, idx)) {
+                idx += 1;
+            }
+            module = format!("{}_{}", base, idx);
+        }
+        write!(root, "\nmod {0};\npub use self::{0}::*;\n", module).unwrap();
+
+        let block = if server {
+            generate_interface_block(iface, options)
+        } else {
+            generate_client_proxy(iface, options)
+        };
+        files.push(GeneratedFile {
+            name: format!("{}.rs", module),
+            code: format!("use super::*;\n\n{}", block),
+        });
+    }
+    if server && options.serve {
+        write!(root, "\n{}", generate_serve(registrations, options)).unwrap();
+    }
+
+    files.insert(
+        0,
+        GeneratedFile {
+            name: "mod.rs".to_string(),
+            code: root,
+        },
+    );
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"
+<node>
+  <interface name="org.example.Foo"><method name="Bar"/></interface>
+  <node name="child">
+    <interface name="org.example_Foo"><method name="Baz"/></interface>
+  </node>
+</node>
+"#;
+
+    #[test]
+    fn module_names_are_unique() {
+        let files = generate_modules_from_xml(XML, Mode::Client, &Options::default());
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["mod.rs", "org_example_foo.rs", "org_example_foo_2.rs"]
+        );
+        assert!(files[0].code.contains("mod org_example_foo_2;"));
+    }
+}
@@ -1,17 +1,36 @@
-use heck::ToSnakeCase;
+use heck::{ToSnakeCase, ToUpperCamelCase};
 use std::collections::HashSet;
 
 const KEYWORDS: &[&str] = &[
     "type", "match", "ref", "mut", "const", "fn", "mod", "pub", "self", "super", "as", "trait",
     "struct", "enum", "impl", "use", "where", "loop", "move", "static", "async", "await", "dyn",
     "crate", "abstract", "final", "macro", "try", "union", "box", "continue", "else", "extern",
-    "false", "for", "if", "in", "let", "return", "true", "unsafe", "while",
+    "false", "for", "if", "in", "let", "return", "true", "unsafe", "while", "Self", "become", "do",
+    "gen", "override", "priv", "typeof", "unsized", "virtual", "yield",
 ];
 
 pub fn to_snake_case(name: &str) -> String {
     name.to_snake_case()
 }
 
+/// A valid Rust identifier for a D-Bus (or XML `name` attribute) name: snake
+/// case, non-identifier characters dropped, keywords escaped.
+pub fn rust_ident(name: &str) -> String {
+    let snake: String = to_snake_case(name)
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    match snake.chars().next() {
+        None => "unnamed".to_string(),
+        Some(c) if c.is_numeric() => format!("_{}", snake),
+        Some(_) => escape_rust_keyword(&snake),
+    }
+}
+
+pub fn to_pascal_case(name: &str) -> String {
+    name.to_upper_camel_case()
+}
+
 pub fn escape_rust_keyword(ident: &str) -> String {
     if KEYWORDS.contains(&ident) {
         format!("{}_", ident)
@@ -68,4 +87,26 @@ mod tests {
             (load_html_page, "loadHTMLPage", "load_html_page"),
         ]
     );
+
+    table_tests!(
+        rust_ident,
+        [
+            (ident_plain, "GetItems", "get_items"),
+            (ident_keyword, "match", "match_"),
+            (ident_stray_chars, "x); drop(y", "x_drop_y"),
+            (ident_leading_digit, "2fa", "_2fa"),
+            (ident_empty, "", "unnamed"),
+            (ident_only_symbols, "!!", "unnamed"),
+        ]
+    );
+
+    table_tests!(
+        to_pascal_case,
+        [
+            (pascal_from_pascal, "PascalCase", "PascalCase"),
+            (pascal_from_lower, "status", "Status"),
+            (pascal_from_snake, "dict_prop", "DictProp"),
+            (pascal_from_kebab, "my-URL", "MyUrl"),
+        ]
+    );
 }
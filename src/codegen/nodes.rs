@@ -0,0 +1,109 @@
+use zbus_xml::{Interface, Node};
+
+/// Every interface in the node tree with the object path it is found at, in
+/// document order. A root node without an absolute name is taken to be `/`.
+pub fn interfaces_by_path<'a, 'n>(node: &'n Node<'a>) -> Vec<(String, &'n Interface<'a>)> {
+    interfaces_at(node, "/")
+}
+
+/// Like [`interfaces_by_path`], taking a root node without an absolute name
+/// to be at `path`, as returned by `Introspect` calls.
+pub fn interfaces_at<'a, 'n>(node: &'n Node<'a>, path: &str) -> Vec<(String, &'n Interface<'a>)> {
+    let root = match node.name() {
+        Some(name) if name.starts_with('/') => name.to_string(),
+        _ => path.to_string(),
+    };
+    let mut out = Vec::new();
+    collect(node, root, &mut out);
+    out
+}
+
+/// The distinct interfaces of the node tree (by name), in document order.
+pub fn unique_interfaces<'a, 'n>(node: &'n Node<'a>) -> Vec<&'n Interface<'a>> {
+    unique_by_name(&interfaces_by_path(node))
+}
+
+/// The distinct interfaces (by name) of `(path, interface)` pairs, in order.
+pub fn unique_by_name<'a, 'n>(
+    registrations: &[(String, &'n Interface<'a>)],
+) -> Vec<&'n Interface<'a>> {
+    let mut out: Vec<&Interface> = Vec::new();
+    for (_, iface) in registrations {
+        if !out.iter().any(|seen| seen.name() == iface.name()) {
+            out.push(iface);
+        }
+    }
+    out
+}
+
+fn collect<'a, 'n>(node: &'n Node<'a>, path: String, out: &mut Vec<(String, &'n Interface<'a>)>) {
+    for iface in node.interfaces() {
+        out.push((path.clone(), iface));
+    }
+    for child in node.nodes() {
+        let child_path = match child.name() {
+            Some(name) if name.starts_with('/') => name.to_string(),
+            Some(name) if path == "/" => format!("/{}", name),
+            Some(name) => format!("{}/{}", path, name),
+            None => path.clone(),
+        };
+        collect(child, child_path, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"
+<node name="/org/example">
+  <interface name="org.example.Root"/>
+  <node name="child">
+    <interface name="org.example.Child"/>
+    <node name="grandchild">
+      <interface name="org.example.Child"/>
+    </node>
+  </node>
+</node>
+"#;
+
+    #[test]
+    fn paths_follow_node_tree() {
+        let node = Node::from_reader(XML.as_bytes()).unwrap();
+        let paths: Vec<_> = interfaces_by_path(&node)
+            .into_iter()
+            .map(|(path, iface)| (path, iface.name().to_string()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("/org/example".to_string(), "org.example.Root".to_string()),
+                (
+                    "/org/example/child".to_string(),
+                    "org.example.Child".to_string()
+                ),
+                (
+                    "/org/example/child/grandchild".to_string(),
+                    "org.example.Child".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unnamed_root_is_slash() {
+        let xml = r#"<node><node name="a"><interface name="org.example.A"/></node></node>"#;
+        let node = Node::from_reader(xml.as_bytes()).unwrap();
+        assert_eq!(interfaces_by_path(&node)[0].0, "/a");
+    }
+
+    #[test]
+    fn unique_interfaces_dedups_by_name() {
+        let node = Node::from_reader(XML.as_bytes()).unwrap();
+        let names: Vec<_> = unique_interfaces(&node)
+            .iter()
+            .map(|iface| iface.name().to_string())
+            .collect();
+        assert_eq!(names, ["org.example.Root", "org.example.Child"]);
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use zbus_xml::Interface;
+
+use crate::codegen::{escape_rust_keyword, to_pascal_case, warn};
+
+/// Method annotation listing the context parameters (see [`ContextParams`])
+/// a server method receives, e.g. `value="header,connection"`.
+pub const CONTEXT_ANNOTATION: &str = "org.zbus.xmlgen.Context";
+
+/// Options controlling the shape of the generated code.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// How the generated server struct holds its delegate.
+    pub dispatch: Dispatch,
+    /// Also generate a `FooSignalHandle` that delegates can hold to emit
+    /// the interface's signals, for interfaces that have any.
+    pub signals: bool,
+    /// Also generate a `FooNotifier` that emits `PropertiesChanged` for the
+    /// interface's properties.
+    pub notifier: bool,
+    /// Context parameters passed to every server method's delegate.
+    pub context: ContextParams,
+    /// Extra context parameters per method, keyed by `interface.Method`
+    /// (e.g. `org.example.Foo.Bar`). Merged with `context` and any
+    /// [`CONTEXT_ANNOTATION`] on the method.
+    pub method_context: BTreeMap<String, ContextParams>,
+    /// Also generate `Delegates` and a `serve()` function registering every
+    /// interface at the object paths of the XML node tree.
+    pub serve: bool,
+    /// Also generate a `MockFooDelegate` answering calls from closures and
+    /// recording them, for tests.
+    pub mock: bool,
+    /// Give delegate trait methods default bodies returning
+    /// `zbus::fdo::Error::NotSupported` (`UnknownProperty` for properties),
+    /// so implementers only override what they support.
+    pub default_bodies: bool,
+    /// Lints to silence in generated files with `#![allow(...)]`, e.g.
+    /// `clippy::all` or `dead_code`.
+    pub allow: Vec<String>,
+    /// Rust types to use for D-Bus signatures instead of the default mapping,
+    /// e.g. `a{sv}` to `crate::Hints`. Matched against whole argument and
+    /// property signatures.
+    pub type_overrides: BTreeMap<String, String>,
+    /// Rust names for interfaces, keyed by D-Bus interface name, replacing
+    /// the last segment of the name (e.g. `Foo` for `org.example.Foo`).
+    pub renames: BTreeMap<String, String>,
+    /// Per-interface overrides of the generation [`Mode`], keyed by D-Bus
+    /// interface name. When interfaces end up in different modes, the output
+    /// takes the [`Mode::Both`] layout with each interface on its sides only.
+    pub interface_modes: BTreeMap<String, Mode>,
+    /// Also generate a `names` module of `zbus::names::InterfaceName` and
+    /// `MemberName` constants for every interface, method, signal and
+    /// property, for building match rules and calls by hand.
+    pub names: bool,
+    /// `default_service` of generated client proxies.
+    pub default_service: Option<String>,
+    /// `default_path` of generated client proxies.
+    pub default_path: Option<String>,
+}
+
+impl Options {
+    /// The Rust base name of an interface: its rename, or the last segment
+    /// of its D-Bus name, keyword-escaped.
+    pub fn interface_rust_name(&self, interface: &str) -> String {
+        let name = match self.renames.get(interface) {
+            Some(rename) => rename.as_str(),
+            None => interface.rsplit('.').next().unwrap_or("Iface"),
+        };
+        escape_rust_keyword(name)
+    }
+
+    /// These options with [`Options::renames`] added for interfaces whose
+    /// Rust names collide, e.g. `org.a.Foo` and `org.b.Foo`: they are named
+    /// after as many trailing segments as it takes (`AFoo` and `BFoo`).
+    pub(crate) fn with_unique_names(&self, interfaces: &[&Interface]) -> Options {
+        let mut by_rust_name: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for iface in interfaces {
+            let name = iface.name().to_string();
+            by_rust_name
+                .entry(self.interface_rust_name(&name))
+                .or_default()
+                .push(name);
+        }
+
+        let mut options = self.clone();
+        for (rust_name, names) in by_rust_name {
+            let renamable: Vec<_> = names
+                .iter()
+                .filter(|name| !self.renames.contains_key(name.as_str()))
+                .collect();
+            if names.len() < 2 || renamable.is_empty() {
+                continue;
+            }
+            let mut segments = 2;
+            let renamed = loop {
+                let renamed: Vec<_> = renamable
+                    .iter()
+                    .map(|name| trailing_segments_name(name, segments))
+                    .collect();
+                let distinct = renamed
+                    .iter()
+                    .enumerate()
+                    .all(|(idx, name)| !renamed[..idx].contains(name) && *name != rust_name);
+                let longest = renamable.iter().map(|n| n.split('.').count()).max();
+                if distinct || Some(segments) >= longest {
+                    break renamed;
+                }
+                segments += 1;
+            };
+            for (name, rename) in renamable.iter().zip(renamed) {
+                warn(format!(
+                    "interface '{}' would be named `{}` like another interface; naming it `{}`",
+                    name, rust_name, rename
+                ));
+                options.renames.insert(name.to_string(), rename);
+            }
+        }
+        options
+    }
+}
+
+/// The last `count` segments of an interface name as one PascalCase name.
+fn trailing_segments_name(interface: &str, count: usize) -> String {
+    let segments: Vec<_> = interface.rsplit('.').take(count).collect();
+    segments.iter().rev().map(|s| to_pascal_case(s)).collect()
+}
+
+/// Which side of the interfaces to generate code for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Mode {
+    /// `#[proxy]` traits.
+    #[default]
+    Client,
+    /// Delegate traits and `#[interface]` structs.
+    Server,
+    /// Both, in `client` and `server` modules sharing the type aliases of a
+    /// `types` module.
+    Both,
+}
+
+/// How server interface structs call into their delegate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Dispatch {
+    /// `Foo { delegate: Arc<dyn FooDelegate> }` with an `#[async_trait]` delegate trait.
+    #[default]
+    Dynamic,
+    /// `Foo<D: FooDelegate>` with `impl Future + Send` trait methods, no boxing.
+    Static,
+}
+
+/// Special parameters zbus can inject into `#[interface]` methods. Selected
+/// ones are handed to the delegate through a generated `FooContext`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextParams {
+    /// The message header, `#[zbus(header)]`.
+    pub header: bool,
+    /// The connection, `#[zbus(connection)]`.
+    pub connection: bool,
+    /// The object server, `#[zbus(object_server)]`.
+    pub object_server: bool,
+    /// A signal emitter for the object path, `#[zbus(signal_emitter)]`.
+    pub emitter: bool,
+}
+
+impl ContextParams {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            header: self.header || other.header,
+            connection: self.connection || other.connection,
+            object_server: self.object_server || other.object_server,
+            emitter: self.emitter || other.emitter,
+        }
+    }
+}
+
+/// Parses a comma-separated list such as `header,connection`.
+impl FromStr for ContextParams {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params = Self::default();
+        for param in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match param {
+                "header" => params.header = true,
+                "connection" => params.connection = true,
+                "object_server" => params.object_server = true,
+                "emitter" | "signal_emitter" => params.emitter = true,
+                _ => return Err(format!("unknown context parameter '{}'", param)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Formats as the comma-separated list [`FromStr`] accepts.
+impl fmt::Display for ContextParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (self.header, "header"),
+            (self.connection, "connection"),
+            (self.object_server, "object_server"),
+            (self.emitter, "emitter"),
+        ];
+        let selected: Vec<_> = names
+            .iter()
+            .filter(|(selected, _)| *selected)
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", selected.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_params_from_str() {
+        let params: ContextParams = "header, signal_emitter".parse().unwrap();
+        assert_eq!(
+            params,
+            ContextParams {
+                header: true,
+                emitter: true,
+                ..ContextParams::default()
+            }
+        );
+        assert!("".parse::<ContextParams>().unwrap().is_empty());
+        assert!("sender".parse::<ContextParams>().is_err());
+        assert_eq!(params.to_string(), "header,emitter");
+    }
+
+    #[test]
+    fn interface_rust_name() {
+        let mut options = Options::default();
+        options
+            .renames
+            .insert("org.example.Foo".to_string(), "Bar".to_string());
+        assert_eq!(options.interface_rust_name("org.example.Foo"), "Bar");
+        assert_eq!(options.interface_rust_name("org.example.Baz"), "Baz");
+        assert_eq!(options.interface_rust_name("org.example.match"), "match_");
+    }
+
+    #[test]
+    fn unique_names() {
+        let xml = r#"
+<node>
+  <interface name="org.freedesktop.login1.Manager"/>
+  <interface name="org.freedesktop.systemd1.Manager"/>
+  <interface name="org.example.Manager"/>
+  <interface name="org.example.Seat"/>
+</node>
+"#;
+        let node = zbus_xml::Node::from_reader(xml.as_bytes()).unwrap();
+        let interfaces: Vec<_> = node.interfaces().iter().collect();
+        let mut options = Options::default();
+        options
+            .renames
+            .insert("org.example.Manager".to_string(), "Manager".to_string());
+
+        let (options, warnings) =
+            crate::codegen::collect_warnings(|| options.with_unique_names(&interfaces));
+        let name = |iface: &str| options.interface_rust_name(iface);
+        assert_eq!(name("org.freedesktop.login1.Manager"), "Login1Manager");
+        assert_eq!(name("org.freedesktop.systemd1.Manager"), "Systemd1Manager");
+        assert_eq!(name("org.example.Manager"), "Manager");
+        assert_eq!(name("org.example.Seat"), "Seat");
+        assert_eq!(warnings.len(), 2);
+    }
+}
@@ -1,95 +1,504 @@
+use std::collections::HashSet;
 use std::fmt::Write;
-use zbus_xml::{ArgDirection, Interface, Method, Node, Property, Signal};
+use zbus_xml::{ArgDirection, Interface, Method, Property, Signal};
 
-use crate::codegen::{dbus_type_to_rust, escape_rust_keyword, to_snake_case};
+use crate::codegen::{
+    dbus_type_to_rust, dbus_type_to_rust_borrowed, generate_from_xml, generate_mock_delegate,
+    rust_ident, to_pascal_case, unique_by_name, warn, ContextParams, Dispatch, Mode, Options,
+    CONTEXT_ANNOTATION,
+};
 
 pub fn generate_server_interface_from_xml(xml: &str) -> String {
-    let node = Node::from_reader(std::io::Cursor::new(xml)).expect("Failed to parse D-Bus XML");
+    generate_server_interface_with_options(xml, &Options::default())
+}
 
-    node.interfaces()
-        .iter()
-        .map(generate_interface_block)
-        .collect::<Vec<_>>()
-        .join("\n")
+pub fn generate_server_interface_with_options(xml: &str, options: &Options) -> String {
+    generate_from_xml(xml, Mode::Server, options)
 }
 
-fn generate_interface_block(interface: &Interface) -> String {
-    let iface_name = interface.name();
-    let struct_name = iface_name.rsplit('.').next().unwrap_or("Iface");
-    let trait_name = format!("{}Delegate", struct_name);
+/// Server code for interfaces registered at the given object paths.
+pub(crate) fn generate_server_interfaces(
+    registrations: &[(String, &Interface)],
+    options: &Options,
+) -> String {
+    let interfaces = unique_by_name(registrations);
+    check_method_context(&interfaces, options);
 
     let mut out = String::new();
+    write!(out, "{}", generate_server_imports(options)).unwrap();
+    for iface in interfaces {
+        write!(out, "\n{}", generate_interface_block(iface, options)).unwrap();
+    }
+    if options.serve {
+        write!(out, "\n{}", generate_serve(registrations, options)).unwrap();
+    }
+    out
+}
 
-    writeln!(out, "use std::sync::Arc;").unwrap();
-    writeln!(out, "use async_trait::async_trait;").unwrap();
+/// Warns about `Options::method_context` keys naming no method of `interfaces`.
+pub(super) fn check_method_context(interfaces: &[&Interface], options: &Options) {
+    for key in options.method_context.keys() {
+        let known = interfaces.iter().any(|iface| {
+            iface
+                .methods()
+                .iter()
+                .any(|m| *key == format!("{}.{}", iface.name(), m.name()))
+        });
+        if !known {
+            warn(format!("method_context key '{}' matches no method", key));
+        }
+    }
+}
+
+pub(super) fn generate_server_imports(options: &Options) -> String {
+    let mut out = String::new();
+
+    if options.dispatch == Dispatch::Dynamic {
+        writeln!(out, "use std::sync::Arc;").unwrap();
+        writeln!(out, "use async_trait::async_trait;").unwrap();
+    }
     writeln!(out, "use zbus::{{interface, Result}};").unwrap();
-    writeln!(out, "use zbus::object_server::SignalEmitter;\n").unwrap();
+    writeln!(out, "use zbus::object_server::SignalEmitter;").unwrap();
+
+    out
+}
+
+/// Rust names derived from one interface, shared by every generated item.
+pub(super) struct ServerNames {
+    pub(super) struct_name: String,
+    pub(super) trait_name: String,
+    pub(super) context_name: String,
+    /// `Foo` or `Foo<D>`, as used in type position.
+    pub(super) struct_ty: String,
+    /// Empty or `<D: FooDelegate>`, for impl blocks.
+    pub(super) impl_generics: String,
+    pub(super) delegate_ty: String,
+}
+
+impl ServerNames {
+    pub(super) fn new(interface: &Interface, options: &Options) -> Self {
+        let struct_name = options.interface_rust_name(interface.name().as_str());
+        let trait_name = format!("{}Delegate", struct_name);
+
+        let (struct_ty, impl_generics, delegate_ty) = match options.dispatch {
+            Dispatch::Dynamic => (
+                struct_name.clone(),
+                String::new(),
+                format!("Arc<dyn {}>", trait_name),
+            ),
+            Dispatch::Static => (
+                format!("{}<D>", struct_name),
+                format!("<D: {}>", trait_name),
+                "D".to_string(),
+            ),
+        };
+
+        Self {
+            context_name: format!("{}Context", struct_name),
+            struct_name,
+            trait_name,
+            struct_ty,
+            impl_generics,
+            delegate_ty,
+        }
+    }
+}
+
+pub(super) fn generate_interface_block(interface: &Interface, options: &Options) -> String {
+    let iface_name = interface.name();
+    let names = ServerNames::new(interface, options);
+    let ServerNames {
+        struct_name,
+        trait_name,
+        struct_ty,
+        impl_generics,
+        delegate_ty,
+        ..
+    } = &names;
+
+    let mut out = String::new();
+
+    // Context
+    if interface
+        .methods()
+        .iter()
+        .any(|m| !method_context(interface, m, options).is_empty())
+    {
+        writeln!(out, "{}", generate_context_struct(&names)).unwrap();
+    }
 
     // Trait
-    writeln!(out, "#[async_trait]").unwrap();
+    if options.dispatch == Dispatch::Dynamic {
+        writeln!(out, "#[async_trait]").unwrap();
+    }
     writeln!(out, "pub trait {}: Send + Sync + 'static {{", trait_name).unwrap();
     for method in interface.methods() {
-        writeln!(out, "{}", generate_trait_method(method)).unwrap();
+        let context = method_context(interface, method, options);
+        writeln!(
+            out,
+            "{}",
+            generate_trait_method(interface, method, context, &names, options)
+        )
+        .unwrap();
     }
     for prop in interface.properties() {
-        write!(out, "{}", generate_trait_property(prop)).unwrap();
+        write!(out, "{}", generate_trait_property(interface, prop, options)).unwrap();
     }
     writeln!(out, "}}\n").unwrap();
 
     // Struct
     writeln!(out, "#[derive(Clone)]").unwrap();
-    writeln!(out, "pub struct {} {{", struct_name).unwrap();
-    writeln!(out, "    pub delegate: Arc<dyn {}>,", trait_name).unwrap();
+    writeln!(out, "pub struct {}{} {{", struct_name, impl_generics).unwrap();
+    writeln!(out, "    pub delegate: {},", delegate_ty).unwrap();
     writeln!(out, "}}\n").unwrap();
 
-    writeln!(out, "impl {} {{", struct_name).unwrap();
-    writeln!(
-        out,
-        "    pub fn new(delegate: Arc<dyn {}>) -> Self {{",
-        trait_name
-    )
-    .unwrap();
+    writeln!(out, "impl{} {} {{", impl_generics, struct_ty).unwrap();
+    writeln!(out, "    pub fn new(delegate: {}) -> Self {{", delegate_ty).unwrap();
     writeln!(out, "        Self {{ delegate }}").unwrap();
     writeln!(out, "    }}").unwrap();
     writeln!(out, "}}\n").unwrap();
 
     // Interface impl
-    writeln!(out, "#[interface(name = \"{}\")]", iface_name).unwrap();
-    writeln!(out, "impl {} {{", struct_name).unwrap();
+    writeln!(out, "#[interface(name = {:?})]", iface_name.as_str()).unwrap();
+    writeln!(out, "impl{} {} {{", impl_generics, struct_ty).unwrap();
     for method in interface.methods() {
-        writeln!(out, "{}", generate_delegate_method(method)).unwrap();
+        let context = method_context(interface, method, options);
+        writeln!(
+            out,
+            "{}",
+            generate_delegate_method(method, context, &names, options)
+        )
+        .unwrap();
     }
     for prop in interface.properties() {
-        writeln!(out, "{}", generate_delegate_property(prop)).unwrap();
+        writeln!(out, "{}", generate_delegate_property(prop, options)).unwrap();
+    }
+    for signal in interface.signals() {
+        writeln!(out, "{}", generate_signal_signature(signal, options)).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+
+    // Signal emission handle
+    if options.signals && !interface.signals().is_empty() {
+        write!(
+            out,
+            "\n{}",
+            generate_signals_handle(interface, &names, options)
+        )
+        .unwrap();
+    }
+
+    // Property change notification
+    if options.notifier && !interface.properties().is_empty() {
+        write!(out, "\n{}", generate_notifier(interface, &names, options)).unwrap();
+    }
+
+    // Mock delegate
+    if options.mock {
+        write!(
+            out,
+            "\n{}",
+            generate_mock_delegate(interface, &names, options)
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+/// `Delegates` and `serve()`, registering each interface at the paths it
+/// appears at in the node tree.
+pub(super) fn generate_serve(registrations: &[(String, &Interface)], options: &Options) -> String {
+    let interfaces = unique_by_name(registrations);
+    let mut out = String::new();
+
+    // Per interface: names, delegate field and how often it is registered.
+    let delegates: Vec<_> = interfaces
+        .iter()
+        .map(|iface| {
+            let names = ServerNames::new(iface, options);
+            let field = rust_ident(&names.struct_name);
+            let count = registrations
+                .iter()
+                .filter(|(_, i)| i.name() == iface.name())
+                .count();
+            (iface.name().to_string(), names, field, count)
+        })
+        .collect();
+
+    // Static dispatch makes `Delegates` generic over one delegate type per interface.
+    let (bounds, params): (Vec<_>, Vec<_>) = match options.dispatch {
+        Dispatch::Dynamic => (Vec::new(), Vec::new()),
+        Dispatch::Static => delegates
+            .iter()
+            .map(|(_, names, _, count)| {
+                let param = format!("{}D", names.struct_name);
+                let clone = if *count > 1 { " + Clone" } else { "" };
+                (format!("{}: {}{}", param, names.trait_name, clone), param)
+            })
+            .unzip(),
+    };
+    let (generics, generic_args) = if params.is_empty() {
+        (String::new(), String::new())
+    } else {
+        (
+            format!("<{}>", bounds.join(", ")),
+            format!("<{}>", params.join(", ")),
+        )
+    };
+    let fn_generics = std::iter::once("'a".to_string())
+        .chain(bounds)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    writeln!(
+        out,
+        "/// The delegates of the interfaces registered by [`serve`]."
+    )
+    .unwrap();
+    writeln!(out, "pub struct Delegates{} {{", generics).unwrap();
+    for (_, names, field, _) in &delegates {
+        let ty = match options.dispatch {
+            Dispatch::Dynamic => names.delegate_ty.clone(),
+            Dispatch::Static => format!("{}D", names.struct_name),
+        };
+        writeln!(out, "    pub {}: {},", field, ty).unwrap();
     }
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(
+        out,
+        "/// Registers every interface at its object path from the introspection XML and,"
+    )
+    .unwrap();
+    writeln!(out, "/// if given, requests the well-known bus `name`.").unwrap();
+    writeln!(out, "pub fn serve<{}>(", fn_generics).unwrap();
+    writeln!(out, "    mut builder: zbus::connection::Builder<'a>,").unwrap();
+    writeln!(out, "    delegates: Delegates{},", generic_args).unwrap();
+    writeln!(out, "    name: Option<&'a str>,").unwrap();
+    writeln!(out, ") -> Result<zbus::connection::Builder<'a>> {{").unwrap();
+
+    let mut remaining: Vec<_> = delegates.iter().map(|(_, _, _, count)| *count).collect();
+    for (path, iface) in registrations {
+        let idx = delegates
+            .iter()
+            .position(|(name, _, _, _)| *name == iface.name().as_str())
+            .unwrap();
+        let (_, names, field, _) = &delegates[idx];
+        remaining[idx] -= 1;
+        let clone = if remaining[idx] > 0 { ".clone()" } else { "" };
+        writeln!(
+            out,
+            "    builder = builder.serve_at({:?}, {}::new(delegates.{}{}))?;",
+            path, names.struct_name, field, clone
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "    if let Some(name) = name {{").unwrap();
+    writeln!(out, "        builder = builder.name(name)?;").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "    Ok(builder)").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+fn generate_signals_handle(
+    interface: &Interface,
+    names: &ServerNames,
+    options: &Options,
+) -> String {
+    // `FooSignals` is already taken by the trait `#[interface]` generates.
+    let handle_name = format!("{}SignalHandle", names.struct_name);
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "/// Emits the signals of `{}` without going through the interface object.",
+        interface.name()
+    )
+    .unwrap();
+    writeln!(out, "#[derive(Clone)]").unwrap();
+    writeln!(out, "pub struct {} {{", handle_name).unwrap();
+    writeln!(out, "    emitter: SignalEmitter<'static>,").unwrap();
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(out, "impl {} {{", handle_name).unwrap();
+    writeln!(
+        out,
+        "    pub fn new<P>(connection: &zbus::Connection, path: P) -> Result<Self>"
+    )
+    .unwrap();
+    writeln!(out, "    where").unwrap();
+    writeln!(
+        out,
+        "        P: std::convert::TryInto<zbus::zvariant::ObjectPath<'static>>,"
+    )
+    .unwrap();
+    writeln!(out, "        P::Error: Into<zbus::Error>,").unwrap();
+    writeln!(out, "    {{").unwrap();
+    writeln!(out, "        Ok(Self {{").unwrap();
+    writeln!(
+        out,
+        "            emitter: SignalEmitter::new(connection, path)?,"
+    )
+    .unwrap();
+    writeln!(out, "        }})").unwrap();
+    writeln!(out, "    }}\n").unwrap();
+
+    writeln!(
+        out,
+        "    pub fn from_interface_ref{}(iface: &zbus::object_server::InterfaceRef<{}>) -> Self {{",
+        names.impl_generics, names.struct_ty
+    )
+    .unwrap();
+    writeln!(out, "        Self {{").unwrap();
+    writeln!(out, "            emitter: iface.signal_emitter().clone(),").unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+
     for signal in interface.signals() {
-        writeln!(out, "{}", generate_signal_signature(signal)).unwrap();
+        write!(
+            out,
+            "\n{}",
+            generate_signal_emit_method(interface, signal, options)
+        )
+        .unwrap();
     }
     writeln!(out, "}}").unwrap();
 
     out
 }
 
-fn generate_trait_method(method: &Method) -> String {
-    let name = escape_rust_keyword(&to_snake_case(&method.name()));
-    let args = method_args(method);
-    let ret = method_return_type(method);
-    format!("    async fn {}(&self{}) -> {};", name, args, ret)
+fn generate_signal_emit_method(
+    interface: &Interface,
+    signal: &Signal,
+    options: &Options,
+) -> String {
+    let name = rust_ident(&signal.name());
+    let (params, arg_names): (Vec<_>, Vec<_>) = signal
+        .args()
+        .iter()
+        .map(|arg| {
+            let name = rust_ident(arg.name().unwrap_or("arg"));
+            let ty = dbus_type_to_rust_borrowed(&arg.ty().to_string(), &options.type_overrides);
+            (format!(", {}: {}", name, ty), name)
+        })
+        .unzip();
+
+    // A single argument stays a one-element tuple, so a struct argument is
+    // not flattened into the message body.
+    let body = match arg_names.len() {
+        1 => format!("({},)", arg_names[0]),
+        _ => format!("({})", arg_names.join(", ")),
+    };
+
+    format!(
+        "    pub async fn {}(&self{}) -> Result<()> {{\n        self.emitter\n            .emit({:?}, {:?}, &{})\n            .await\n    }}\n",
+        name,
+        params.concat(),
+        interface.name().as_str(),
+        signal.name().as_str(),
+        body
+    )
+}
+
+/// Declaration of a delegate trait method: `async fn` for dynamic dispatch,
+/// an `impl Future + Send` return for static dispatch.
+fn trait_fn_decl(name: &str, args: &str, ret: &str, options: &Options) -> String {
+    match options.dispatch {
+        Dispatch::Dynamic => format!("async fn {}(&self{}) -> {}", name, args, ret),
+        Dispatch::Static => format!(
+            "fn {}(&self{}) -> impl std::future::Future<Output = {}> + Send",
+            name, args, ret
+        ),
+    }
 }
 
-fn generate_trait_property(prop: &Property) -> String {
-    let name = escape_rust_keyword(&to_snake_case(&prop.name()));
-    let ty = dbus_type_to_rust(&prop.ty().to_string());
+/// A delegate trait method, with `default_body` as its body when
+/// [`Options::default_bodies`] is set.
+fn trait_fn_item(
+    name: &str,
+    args: &[(String, String)],
+    ret: &str,
+    default_body: &str,
+    options: &Options,
+) -> String {
+    // Default bodies leave the arguments unused.
+    let prefix = if options.default_bodies { "_" } else { "" };
+    let args: String = args
+        .iter()
+        .map(|(name, ty)| format!(", {}{}: {}", prefix, name, ty))
+        .collect();
+    let decl = trait_fn_decl(name, &args, ret, options);
+
+    if !options.default_bodies {
+        return format!("    {};", decl);
+    }
+    match options.dispatch {
+        Dispatch::Dynamic => format!("    {} {{\n        {}\n    }}", decl, default_body),
+        Dispatch::Static => format!(
+            "    {} {{\n        async {{ {} }}\n    }}",
+            decl, default_body
+        ),
+    }
+}
+
+/// The return type of a property getter: fallible when the delegate may not
+/// implement it.
+pub(super) fn property_getter_type(ty: &str, options: &Options) -> String {
+    if options.default_bodies {
+        format!("zbus::fdo::Result<{}>", ty)
+    } else {
+        ty.to_string()
+    }
+}
+
+fn generate_trait_method(
+    interface: &Interface,
+    method: &Method,
+    context: ContextParams,
+    names: &ServerNames,
+    options: &Options,
+) -> String {
+    let name = rust_ident(&method.name());
+    let mut args = method_in_args(method, options);
+    if !context.is_empty() {
+        let ctx_ty = format!("{}<'_>", names.context_name);
+        args.insert(0, ("ctx".to_string(), ctx_ty));
+    }
+    let ret = method_return_type(method, options);
+    let message = format!("{}.{} is not supported", interface.name(), method.name());
+    let body = format!("Err(zbus::fdo::Error::NotSupported({:?}.into()))", message);
+    trait_fn_item(&name, &args, &ret, &body, options)
+}
+
+fn generate_trait_property(interface: &Interface, prop: &Property, options: &Options) -> String {
+    let name = rust_ident(&prop.name());
+    let ty = dbus_type_to_rust(&prop.ty().to_string(), &options.type_overrides);
+    let message = format!("{}.{}", interface.name(), prop.name());
+    let unknown = format!("zbus::fdo::Error::UnknownProperty({:?}.into())", message);
     let mut out = String::new();
 
     if prop.access().read() {
-        writeln!(&mut out, "    async fn {}(&self) -> {};", name, ty).unwrap();
+        let ret = property_getter_type(&ty, options);
+        let body = format!("Err({})", unknown);
+        writeln!(
+            &mut out,
+            "{}",
+            trait_fn_item(&name, &[], &ret, &body, options)
+        )
+        .unwrap();
     }
     if prop.access().write() {
+        let setter = format!("set_{}", name);
+        let args = [("val".to_string(), ty)];
+        let body = format!("Err({}.into())", unknown);
         writeln!(
             &mut out,
-            "    async fn set_{}(&self, val: {}) -> Result<()>;",
-            name, ty
+            "{}",
+            trait_fn_item(&setter, &args, "Result<()>", &body, options)
         )
         .unwrap();
     }
@@ -97,32 +506,70 @@ fn generate_trait_property(prop: &Property) -> String {
     out
 }
 
-fn generate_delegate_method(method: &Method) -> String {
-    let name = escape_rust_keyword(&to_snake_case(&method.name()));
-    let args = method_args(method);
-    let ret = method_return_type(method);
-    let call_args = method_arg_names(method);
+fn generate_delegate_method(
+    method: &Method,
+    context: ContextParams,
+    names: &ServerNames,
+    options: &Options,
+) -> String {
+    let name = rust_ident(&method.name());
+    let mut args = method_args(method, options);
+    let ret = method_return_type(method, options);
+    let mut call_args = method_arg_names(method, options);
+
+    if context.is_empty() {
+        return format!(
+            "    async fn {}(&self{}) -> {} {{\n        self.delegate.{}({}).await\n    }}\n",
+            name, args, ret, name, call_args
+        );
+    }
+
+    let mut fields = String::new();
+    for (selected, field, attr, ty) in CONTEXT_PARAMS {
+        let value = if selected(&context) {
+            write!(args, ", #[zbus({})] zbus_{}: {}", attr, field, ty).unwrap();
+            format!("Some(zbus_{})", field)
+        } else {
+            "None".to_string()
+        };
+        writeln!(fields, "            {}: {},", field, value).unwrap();
+    }
+    call_args = if call_args.is_empty() {
+        "ctx".to_string()
+    } else {
+        format!("ctx, {}", call_args)
+    };
 
     format!(
-        "    async fn {}(&self{}) -> {} {{\n        self.delegate.{}({}).await\n    }}\n",
-        name, args, ret, name, call_args
+        "    async fn {}(&self{}) -> {} {{\n        let ctx = {} {{\n{}        }};\n        self.delegate.{}({}).await\n    }}\n",
+        name, args, ret, names.context_name, fields, name, call_args
     )
 }
 
-fn generate_delegate_property(prop: &Property) -> String {
-    let name = escape_rust_keyword(&to_snake_case(&prop.name()));
-    let ty = dbus_type_to_rust(&prop.ty().to_string());
+fn generate_delegate_property(prop: &Property, options: &Options) -> String {
+    let name = rust_ident(&prop.name());
+    let ty = dbus_type_to_rust(&prop.ty().to_string(), &options.type_overrides);
     let mut out = String::new();
 
     if prop.access().read() {
         writeln!(
             &mut out,
             "    #[zbus(property)]\n    async fn {}(&self) -> {} {{\n        self.delegate.{}().await\n    }}",
-            name, ty, name
+            name,
+            property_getter_type(&ty, options),
+            name
         )
         .unwrap();
     }
-    if prop.access().write() {
+    if prop.access().write() && options.default_bodies {
+        // The delegate may not support the property, so report its error.
+        writeln!(
+            &mut out,
+            "    #[zbus(property)]\n    async fn set_{}(&mut self, val: {}) -> zbus::fdo::Result<()> {{\n        self.delegate.set_{}(val).await.map_err(Into::into)\n    }}",
+            name, ty, name
+        )
+        .unwrap();
+    } else if prop.access().write() {
         writeln!(
             &mut out,
             "    #[zbus(property)]\n    async fn set_{}(&mut self, val: {}) {{\n        let _ = self.delegate.set_{}(val).await;\n    }}",
@@ -134,14 +581,14 @@ fn generate_delegate_property(prop: &Property) -> String {
     out
 }
 
-fn generate_signal_signature(signal: &Signal) -> String {
-    let name = to_snake_case(&signal.name());
+fn generate_signal_signature(signal: &Signal, options: &Options) -> String {
+    let name = rust_ident(&signal.name());
     let args: Vec<_> = signal
         .args()
         .iter()
         .map(|arg| {
-            let name = escape_rust_keyword(&to_snake_case(arg.name().unwrap_or("arg")));
-            let ty = dbus_type_to_rust(&arg.ty().to_string());
+            let name = rust_ident(arg.name().unwrap_or("arg"));
+            let ty = dbus_type_to_rust(&arg.ty().to_string(), &options.type_overrides);
             format!("{}: {}", name, ty)
         })
         .collect();
@@ -158,36 +605,263 @@ fn generate_signal_signature(signal: &Signal) -> String {
     )
 }
 
-fn method_args(method: &Method) -> String {
-    let args: Vec<_> = method
-        .args()
+fn generate_notifier(interface: &Interface, names: &ServerNames, options: &Options) -> String {
+    let enum_name = format!("{}Property", names.struct_name);
+    let notifier_name = format!("{}Notifier", names.struct_name);
+    let notifier_ty = format!(
+        "{}{}",
+        notifier_name,
+        if options.dispatch == Dispatch::Static {
+            "<D>"
+        } else {
+            ""
+        }
+    );
+    let mut out = String::new();
+
+    // Property enum, for batched invalidation
+    let mut used_variants = HashSet::new();
+    let variants: Vec<_> = interface
+        .properties()
         .iter()
-        .filter(|a| a.direction() == Some(ArgDirection::In))
-        .map(|a| {
-            let name = escape_rust_keyword(&to_snake_case(a.name().unwrap_or("arg")));
-            let ty = dbus_type_to_rust(&a.ty().to_string());
-            format!("{}: {}", name, ty)
+        .map(|prop| {
+            let base = to_pascal_case(&prop.name());
+            let mut variant = base.clone();
+            let mut idx = 2;
+            while !used_variants.insert(variant.clone()) {
+                variant = format!("{}{}", base, idx);
+                idx += 1;
+            }
+            (variant, prop.name().to_string())
         })
         .collect();
 
-    if args.is_empty() {
-        "".into()
-    } else {
-        format!(", {}", args.join(", "))
+    writeln!(out, "/// The properties of `{}`.", interface.name()).unwrap();
+    writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]").unwrap();
+    writeln!(out, "pub enum {} {{", enum_name).unwrap();
+    for (variant, _) in &variants {
+        writeln!(out, "    {},", variant).unwrap();
+    }
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(out, "impl {} {{", enum_name).unwrap();
+    writeln!(out, "    /// The D-Bus name of the property.").unwrap();
+    writeln!(out, "    pub fn name(&self) -> &'static str {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for (variant, dbus_name) in &variants {
+        writeln!(out, "            Self::{} => {:?},", variant, dbus_name).unwrap();
+    }
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}\n").unwrap();
+
+    // Notifier
+    writeln!(
+        out,
+        "/// Emits `PropertiesChanged` for the properties of `{}`.",
+        interface.name()
+    )
+    .unwrap();
+    if options.dispatch == Dispatch::Dynamic {
+        writeln!(out, "#[derive(Clone)]").unwrap();
+    }
+    writeln!(
+        out,
+        "pub struct {}{} {{",
+        notifier_name, names.impl_generics
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    iface: zbus::object_server::InterfaceRef<{}>,",
+        names.struct_ty
+    )
+    .unwrap();
+    writeln!(out, "}}\n").unwrap();
+
+    writeln!(out, "impl{} {} {{", names.impl_generics, notifier_ty).unwrap();
+    writeln!(
+        out,
+        "    pub fn new(iface: zbus::object_server::InterfaceRef<{}>) -> Self {{",
+        names.struct_ty
+    )
+    .unwrap();
+    writeln!(out, "        Self {{ iface }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+
+    for prop in interface.properties().iter().filter(|p| p.access().read()) {
+        let name = rust_ident(&prop.name());
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "    /// Emits `PropertiesChanged` with the current value of `{}`.",
+            prop.name()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    pub async fn {}_changed(&self) -> Result<()> {{",
+            name
+        )
+        .unwrap();
+        writeln!(out, "        let iface = self.iface.get().await;").unwrap();
+        writeln!(
+            out,
+            "        iface.{}_changed(self.iface.signal_emitter()).await",
+            name
+        )
+        .unwrap();
+        writeln!(out, "    }}").unwrap();
+    }
+
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "    /// Emits a single `PropertiesChanged` invalidating all of `properties`."
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    pub async fn invalidate(&self, properties: &[{}]) -> Result<()> {{",
+        enum_name
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "        let names: Vec<&str> = properties.iter().map(|p| p.name()).collect();"
+    )
+    .unwrap();
+    writeln!(out, "        zbus::fdo::Properties::properties_changed(").unwrap();
+    writeln!(out, "            self.iface.signal_emitter(),").unwrap();
+    writeln!(
+        out,
+        "            zbus::names::InterfaceName::from_static_str_unchecked({:?}),",
+        interface.name().as_str()
+    )
+    .unwrap();
+    writeln!(out, "            std::collections::HashMap::new(),").unwrap();
+    writeln!(out, "            names.into(),").unwrap();
+    writeln!(out, "        )").unwrap();
+    writeln!(out, "        .await").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+/// Context parameters: whether it is selected, the `FooContext` field, the
+/// `#[zbus(..)]` attribute and the parameter type in the `#[interface]` method.
+type ContextParam = (
+    fn(&ContextParams) -> bool,
+    &'static str,
+    &'static str,
+    &'static str,
+);
+
+const CONTEXT_PARAMS: [ContextParam; 4] = [
+    (
+        |c| c.header,
+        "header",
+        "header",
+        "zbus::message::Header<'_>",
+    ),
+    (
+        |c| c.connection,
+        "connection",
+        "connection",
+        "&zbus::Connection",
+    ),
+    (
+        |c| c.object_server,
+        "object_server",
+        "object_server",
+        "&zbus::ObjectServer",
+    ),
+    (
+        |c| c.emitter,
+        "emitter",
+        "signal_emitter",
+        "SignalEmitter<'_>",
+    ),
+];
+
+/// The context parameters of a method: the global ones, plus any set for it
+/// in the options or through [`CONTEXT_ANNOTATION`].
+pub(super) fn method_context(
+    interface: &Interface,
+    method: &Method,
+    options: &Options,
+) -> ContextParams {
+    let key = format!("{}.{}", interface.name(), method.name());
+    let mut context = options.context;
+    if let Some(extra) = options.method_context.get(&key) {
+        context = context.union(*extra);
+    }
+    for annotation in method.annotations() {
+        if annotation.name() != CONTEXT_ANNOTATION {
+            continue;
+        }
+        match annotation.value().parse::<ContextParams>() {
+            Ok(extra) => context = context.union(extra),
+            Err(err) => warn(format!("{} on {}: {}", CONTEXT_ANNOTATION, key, err)),
+        }
     }
+    context
 }
 
-fn method_arg_names(method: &Method) -> String {
+fn generate_context_struct(names: &ServerNames) -> String {
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "/// Call context passed to `{}` methods. Fields not requested for a method are `None`.",
+        names.trait_name
+    )
+    .unwrap();
+    writeln!(out, "pub struct {}<'a> {{", names.context_name).unwrap();
+    writeln!(out, "    pub header: Option<zbus::message::Header<'a>>,").unwrap();
+    writeln!(out, "    pub connection: Option<&'a zbus::Connection>,").unwrap();
+    writeln!(
+        out,
+        "    pub object_server: Option<&'a zbus::ObjectServer>,"
+    )
+    .unwrap();
+    writeln!(out, "    pub emitter: Option<SignalEmitter<'a>>,").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+/// Names and Rust types of a method's in-args.
+pub(super) fn method_in_args(method: &Method, options: &Options) -> Vec<(String, String)> {
     method
         .args()
         .iter()
         .filter(|a| a.direction() == Some(ArgDirection::In))
-        .map(|a| escape_rust_keyword(&to_snake_case(a.name().unwrap_or("arg"))))
+        .map(|a| {
+            let name = rust_ident(a.name().unwrap_or("arg"));
+            let ty = dbus_type_to_rust(&a.ty().to_string(), &options.type_overrides);
+            (name, ty)
+        })
+        .collect()
+}
+
+fn method_args(method: &Method, options: &Options) -> String {
+    method_in_args(method, options)
+        .iter()
+        .map(|(name, ty)| format!(", {}: {}", name, ty))
+        .collect()
+}
+
+fn method_arg_names(method: &Method, options: &Options) -> String {
+    method_in_args(method, options)
+        .into_iter()
+        .map(|(name, _)| name)
         .collect::<Vec<_>>()
         .join(", ")
 }
 
-fn method_return_type(method: &Method) -> String {
+pub(super) fn method_return_type(method: &Method, options: &Options) -> String {
     let out_args: Vec<_> = method
         .args()
         .iter()
@@ -198,13 +872,13 @@ fn method_return_type(method: &Method) -> String {
         0 => "zbus::fdo::Result<()>".into(),
         1 => format!(
             "zbus::fdo::Result<{}>",
-            dbus_type_to_rust(&out_args[0].ty().to_string())
+            dbus_type_to_rust(&out_args[0].ty().to_string(), &options.type_overrides)
         ),
         _ => format!(
             "zbus::fdo::Result<({})>",
             out_args
                 .iter()
-                .map(|a| dbus_type_to_rust(&a.ty().to_string()))
+                .map(|a| dbus_type_to_rust(&a.ty().to_string(), &options.type_overrides))
                 .collect::<Vec<_>>()
                 .join(", ")
         ),
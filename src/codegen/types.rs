@@ -1,8 +1,35 @@
-pub fn dbus_type_to_rust(ty: &str) -> String {
+use std::collections::BTreeMap;
+
+use crate::codegen::warn;
+
+/// The Rust type of a D-Bus signature. Whole signatures found in `named`
+/// (type overrides and shared type aliases) map to the type given there.
+pub fn dbus_type_to_rust(ty: &str, named: &BTreeMap<String, String>) -> String {
+    if let Some(named) = named.get(ty) {
+        return named.clone();
+    }
     let mut chars = ty.chars().peekable();
     parse_dbus_type(&mut chars)
 }
 
+/// Like [`dbus_type_to_rust`], but borrows strings and containers, for
+/// parameters that are only serialized (e.g. signal emission).
+pub fn dbus_type_to_rust_borrowed(ty: &str, named: &BTreeMap<String, String>) -> String {
+    if let Some(named) = named.get(ty) {
+        return if ty.starts_with('a') {
+            format!("&{}", named)
+        } else {
+            named.clone()
+        };
+    }
+    match ty.chars().next() {
+        Some('s') => "&str".into(),
+        Some('a') if !ty.starts_with("a{") => format!("&[{}]", dbus_type_to_rust(&ty[1..], named)),
+        Some('a') | Some('o') | Some('g') => format!("&{}", dbus_type_to_rust(ty, named)),
+        _ => dbus_type_to_rust(ty, named),
+    }
+}
+
 fn parse_dbus_type<I>(chars: &mut std::iter::Peekable<I>) -> String
 where
     I: Iterator<Item = char>,
@@ -45,7 +72,7 @@ where
             format!("({})", fields.join(", "))
         }
         Some(ch) => {
-            eprintln!("Warning: unknown D-Bus type '{}'", ch);
+            warn(format!("unknown D-Bus type '{}'", ch));
             "zbus::zvariant::Value<'_>".into()
         }
         None => "zbus::zvariant::Value<'_>".into(),
@@ -61,7 +88,8 @@ mod tests {
             $(
                 #[test]
                 fn $name() {
-                    assert_eq!($func($input), $expected, "input: {:?}", $input);
+                    let named = BTreeMap::new();
+                    assert_eq!($func($input, &named), $expected, "input: {:?}", $input);
                 }
             )*
         };
@@ -116,4 +144,39 @@ mod tests {
             (type_empty, "", "zbus::zvariant::Value<'_>")
         ]
     );
+
+    table_tests!(
+        dbus_type_to_rust_borrowed,
+        [
+            (borrowed_s, "s", "&str"),
+            (borrowed_i, "i", "i32"),
+            (borrowed_o, "o", "&zbus::zvariant::ObjectPath<'_>"),
+            (borrowed_as, "as", "&[String]"),
+            (borrowed_a_struct, "a(ii)", "&[(i32, i32)]"),
+            (
+                borrowed_dict,
+                "a{sv}",
+                "&std::collections::HashMap<String, zbus::zvariant::Value<'_>>"
+            ),
+            (borrowed_struct, "(si)", "(String, i32)"),
+        ]
+    );
+
+    #[test]
+    fn named_types() {
+        let named = vec![
+            ("a(ii)".to_string(), "Points".to_string()),
+            ("(ii)".to_string(), "Point".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(dbus_type_to_rust("a(ii)", &named), "Points");
+        assert_eq!(dbus_type_to_rust("aa(ii)", &named), "Vec<Vec<(i32, i32)>>");
+        assert_eq!(dbus_type_to_rust_borrowed("a(ii)", &named), "&Points");
+        assert_eq!(dbus_type_to_rust_borrowed("(ii)", &named), "Point");
+        assert_eq!(
+            dbus_type_to_rust("a(ii)", &BTreeMap::new()),
+            "Vec<(i32, i32)>"
+        );
+    }
 }
@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+use zbus_xml::Interface;
+
+use crate::codegen::{
+    generate, generate_modules, warn, ContextParams, Dispatch, GeneratedFile, Mode, Options,
+};
+use crate::sources::{generate_sources_header, matches_glob, merged_registrations, read_sources};
+use crate::{Error, Level, LintLevels};
+
+/// The configuration file `zbus-xml-gen` looks for in the working directory.
+pub const CONFIG_FILE: &str = "zbus-xml-gen.toml";
+
+/// A `zbus-xml-gen.toml` project configuration, with one `[[output]]` table
+/// per generated file or directory:
+///
+/// ```toml
+/// [[output]]
+/// inputs = ["interfaces"]
+/// out-dir = "src/bindings"
+/// mode = "both"
+/// signals = true
+/// exclude = ["org.freedesktop.DBus.*"]
+/// service = "org.example.Foo"
+///
+/// [output.types]
+/// "a{sv}" = "crate::Hints"
+///
+/// [output.interfaces."org.example.Foo1"]
+/// rename = "Foo"
+/// mode = "server"
+///
+/// [lint]
+/// allow = ["member-case"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default, rename = "output")]
+    pub outputs: Vec<OutputConfig>,
+    #[serde(default)]
+    pub lint: LintConfig,
+    /// The file the configuration was read from by [`Config::from_file`],
+    /// named in errors.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+/// One `[[output]]` of a [`Config`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct OutputConfig {
+    /// XML files, directories of `*.xml` files and glob patterns, relative to
+    /// the configuration file.
+    pub inputs: Vec<PathBuf>,
+    /// Directory to write `mod.rs` and one module per interface into.
+    pub out_dir: Option<PathBuf>,
+    /// File to write all code into, instead of `out_dir`.
+    pub file: Option<PathBuf>,
+    pub mode: Mode,
+    pub dispatch: Dispatch,
+    pub signals: bool,
+    pub notifier: bool,
+    #[serde(deserialize_with = "context_params")]
+    pub context: ContextParams,
+    pub serve: bool,
+    pub mock: bool,
+    pub default_bodies: bool,
+    pub names: bool,
+    pub allow: Vec<String>,
+    /// Interface name patterns to generate, where `*` matches any
+    /// characters and `?` any one character. Empty means all interfaces.
+    pub include: Vec<String>,
+    /// Interface name patterns not to generate.
+    pub exclude: Vec<String>,
+    /// Rust types for D-Bus signatures, see [`Options::type_overrides`].
+    pub types: BTreeMap<String, String>,
+    /// Default service of client proxies.
+    pub service: Option<String>,
+    /// Default object path of client proxies.
+    pub path: Option<String>,
+    /// Per-interface settings, keyed by D-Bus interface name.
+    pub interfaces: BTreeMap<String, InterfaceConfig>,
+}
+
+/// Settings for one interface of an [`OutputConfig`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InterfaceConfig {
+    /// Overrides the output's mode for this interface.
+    pub mode: Option<Mode>,
+    /// Rust name replacing the last segment of the interface name.
+    pub rename: Option<String>,
+}
+
+/// The `[lint]` table of a [`Config`]: rule names (or `all`) per level,
+/// applied in the order allow, warn, deny.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    pub allow: Vec<String>,
+    pub warn: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl LintConfig {
+    pub fn levels(&self) -> Result<LintLevels, String> {
+        let mut levels = LintLevels::default();
+        for (rules, level) in [
+            (&self.allow, Level::Allow),
+            (&self.warn, Level::Warn),
+            (&self.deny, Level::Deny),
+        ]
+        .iter()
+        {
+            for rule in rules.iter() {
+                levels.set(rule, *level)?;
+            }
+        }
+        Ok(levels)
+    }
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let config_error = |message: String| Error::Config {
+            path: path.to_path_buf(),
+            message,
+        };
+        let mut config: Config = text
+            .parse()
+            .map_err(|err: toml::de::Error| config_error(err.to_string()))?;
+        config.lint.levels().map_err(config_error)?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// The inputs of every output, relative to `root`.
+    pub fn inputs(&self, root: &Path) -> Vec<PathBuf> {
+        self.outputs
+            .iter()
+            .flat_map(|output| output.inputs.iter().map(|input| root.join(input)))
+            .collect()
+    }
+
+    /// Generates every output. Paths in the configuration and of the returned
+    /// files are relative to `root`, the directory of the configuration file.
+    /// Files are formatted and start with a provenance header. Warnings go
+    /// through the usual diagnostics.
+    pub fn generate(&self, root: &Path) -> Result<Vec<GeneratedFile>, Error> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => root.join(CONFIG_FILE),
+        };
+        let mut files = Vec::new();
+        for output in &self.outputs {
+            files.extend(output.generate(root, &path)?);
+        }
+        Ok(files)
+    }
+}
+
+impl FromStr for Config {
+    type Err = toml::de::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s)
+    }
+}
+
+impl OutputConfig {
+    /// The generator options this output selects.
+    pub fn options(&self) -> Options {
+        let mut options = Options {
+            dispatch: self.dispatch,
+            signals: self.signals,
+            notifier: self.notifier,
+            context: self.context,
+            serve: self.serve,
+            mock: self.mock,
+            default_bodies: self.default_bodies,
+            names: self.names,
+            allow: self.allow.clone(),
+            type_overrides: self.types.clone(),
+            default_service: self.service.clone(),
+            default_path: self.path.clone(),
+            ..Options::default()
+        };
+        for (name, interface) in &self.interfaces {
+            if let Some(mode) = interface.mode {
+                options.interface_modes.insert(name.clone(), mode);
+            }
+            if let Some(rename) = &interface.rename {
+                options.renames.insert(name.clone(), rename.clone());
+            }
+        }
+        options
+    }
+
+    /// Generates the output, reporting configuration errors against the
+    /// configuration file at `path`.
+    fn generate(&self, root: &Path, path: &Path) -> Result<Vec<GeneratedFile>, Error> {
+        let config_error = |message: &str| Error::Config {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        };
+        if self.inputs.is_empty() {
+            return Err(config_error("an output has no inputs"));
+        }
+
+        let inputs: Vec<_> = self.inputs.iter().map(|input| root.join(input)).collect();
+        let mut sources = read_sources(&inputs)?;
+        for source in &mut sources {
+            // Record paths in headers as written in the configuration.
+            if let Ok(path) = source.path.strip_prefix(root) {
+                source.path = path.to_path_buf();
+            }
+        }
+        let registrations: Vec<_> = merged_registrations(&sources)?
+            .into_iter()
+            .filter(|(_, iface)| self.selects(iface))
+            .collect();
+        for name in self.interfaces.keys() {
+            if !registrations
+                .iter()
+                .any(|(_, iface)| iface.name() == name.as_str())
+            {
+                warn(format!("configured interface '{}' is not generated", name));
+            }
+        }
+
+        let options = self.options();
+        let header = generate_sources_header(&sources, self.mode, &options);
+        let files = match (&self.out_dir, &self.file) {
+            (Some(out_dir), None) => generate_modules(&registrations, self.mode, &options)
+                .map_err(|err| match err {
+                    Error::InvalidCode { path, message } => Error::InvalidCode {
+                        path: root.join(out_dir).join(path),
+                        message,
+                    },
+                    err => err,
+                })?
+                .into_iter()
+                .map(|file| GeneratedFile {
+                    name: out_dir.join(&file.name).to_string_lossy().into_owned(),
+                    code: file.code,
+                })
+                .collect(),
+            (None, Some(file)) => vec![GeneratedFile {
+                name: file.to_string_lossy().into_owned(),
+                code: generate(&registrations, self.mode, &options).map_err(|err| {
+                    Error::InvalidCode {
+                        path: root.join(file),
+                        message: err.to_string(),
+                    }
+                })?,
+            }],
+            _ => return Err(config_error("an output needs either `out-dir` or `file`")),
+        };
+
+        Ok(files
+            .into_iter()
+            .map(|file| GeneratedFile {
+                code: format!("{}{}", header, file.code),
+                ..file
+            })
+            .collect())
+    }
+
+    fn selects(&self, interface: &Interface) -> bool {
+        let name = interface.name();
+        let included =
+            self.include.is_empty() || self.include.iter().any(|p| matches_glob(p, name.as_str()));
+        included && !self.exclude.iter().any(|p| matches_glob(p, name.as_str()))
+    }
+}
+
+fn context_params<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ContextParams, D::Error> {
+    let params = String::deserialize(deserializer)?;
+    params.parse().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_config() {
+        let config: Config = r#"
+[[output]]
+inputs = ["interfaces"]
+file = "src/bindings.rs"
+mode = "server"
+dispatch = "static"
+context = "header,connection"
+types = { "a{sv}" = "crate::Hints" }
+
+[output.interfaces."org.example.Foo1"]
+rename = "Foo"
+mode = "both"
+"#
+        .parse()
+        .unwrap();
+
+        let options = config.outputs[0].options();
+        assert_eq!(config.outputs[0].mode, Mode::Server);
+        assert_eq!(options.dispatch, Dispatch::Static);
+        assert!(options.context.header && options.context.connection);
+        assert_eq!(options.type_overrides["a{sv}"], "crate::Hints");
+        assert_eq!(options.renames["org.example.Foo1"], "Foo");
+        assert_eq!(options.interface_modes["org.example.Foo1"], Mode::Both);
+    }
+
+    #[test]
+    fn lint_levels() {
+        let config: Config = "[lint]\nallow = [\"all\"]\ndeny = [\"member-case\"]\n"
+            .parse()
+            .unwrap();
+        let levels = config.lint.levels().unwrap();
+        assert_eq!(levels.level(crate::Rule::InvalidName), Level::Allow);
+        assert_eq!(levels.level(crate::Rule::MemberCase), Level::Deny);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!("[[output]]\nsignal = true\n".parse::<Config>().is_err());
+        assert!("[[output]]\ncontext = \"sender\"\n"
+            .parse::<Config>()
+            .is_err());
+    }
+}
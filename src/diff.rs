@@ -0,0 +1,421 @@
+use std::fmt;
+
+use zbus_xml::{Annotation, Arg, ArgDirection, Interface, Node, PropertyAccess};
+
+use crate::codegen::{interfaces_by_path, unique_by_name};
+use crate::sources::{merged_registrations, Source};
+use crate::Error;
+
+/// Whether existing clients keep working after a [`Change`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Compatibility {
+    Compatible,
+    Breaking,
+}
+
+/// What a [`Change`] changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "json",
+    derive(serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum ChangeKind {
+    InterfaceAdded,
+    InterfaceRemoved,
+    /// The interface is no longer, or now also, at an object path.
+    ObjectPathRemoved,
+    ObjectPathAdded,
+    MethodAdded,
+    MethodRemoved,
+    /// Input arguments were added, removed or changed type.
+    MethodInArgsChanged,
+    /// Output arguments were added, removed or changed type.
+    MethodOutArgsChanged,
+    /// Arguments kept their types but not their names.
+    ArgsRenamed,
+    SignalAdded,
+    SignalRemoved,
+    SignalArgsChanged,
+    PropertyAdded,
+    PropertyRemoved,
+    PropertyTypeChanged,
+    PropertyAccessChanged,
+    /// `org.freedesktop.DBus.Property.EmitsChangedSignal` changed.
+    PropertyEmitsChanged,
+    /// `org.freedesktop.DBus.Deprecated` was added or removed.
+    DeprecationChanged,
+}
+
+/// A difference between two versions of introspection data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct Change {
+    pub compatibility: Compatibility,
+    pub kind: ChangeKind,
+    pub interface: String,
+    /// The method, signal or property changed, if any.
+    pub member: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let compatibility = match self.compatibility {
+            Compatibility::Compatible => "compatible",
+            Compatibility::Breaking => "breaking",
+        };
+        write!(f, "{}: {}", compatibility, self.interface)?;
+        if let Some(member) = &self.member {
+            write!(f, ".{}", member)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The changes from `old` to `new` XML, classified by whether they break
+/// existing clients.
+pub fn diff_xml(old: &str, new: &str) -> Vec<Change> {
+    let parse = |xml: &str| Node::from_reader(xml.as_bytes()).expect("Failed to parse D-Bus XML");
+    let (old, new) = (parse(old), parse(new));
+    diff(&interfaces_by_path(&old), &interfaces_by_path(&new))
+}
+
+/// Like [`diff_xml`], for the interfaces of several sources on each side.
+pub fn diff_sources(old: &[Source], new: &[Source]) -> Result<Vec<Change>, Error> {
+    Ok(diff(
+        &merged_registrations(old)?,
+        &merged_registrations(new)?,
+    ))
+}
+
+fn diff(old: &[(String, &Interface)], new: &[(String, &Interface)]) -> Vec<Change> {
+    let mut changes = Changes::default();
+    let new_interfaces = unique_by_name(new);
+    for old_iface in unique_by_name(old) {
+        let name = old_iface.name();
+        match new_interfaces.iter().find(|iface| iface.name() == name) {
+            Some(new_iface) => {
+                let paths = |registrations: &[(String, &Interface)]| -> Vec<String> {
+                    registrations
+                        .iter()
+                        .filter(|(_, iface)| iface.name() == name)
+                        .map(|(path, _)| path.clone())
+                        .collect()
+                };
+                let (old_paths, new_paths) = (paths(old), paths(new));
+                let mut changes = changes.interface(&name);
+                for path in old_paths.iter().filter(|p| !new_paths.contains(p)) {
+                    changes.breaking(
+                        ChangeKind::ObjectPathRemoved,
+                        format!("no longer at {}", path),
+                    );
+                }
+                for path in new_paths.iter().filter(|p| !old_paths.contains(p)) {
+                    changes
+                        .compatible(ChangeKind::ObjectPathAdded, format!("now also at {}", path));
+                }
+                diff_interface(old_iface, new_iface, &mut changes);
+            }
+            None => changes.interface(&name).breaking(
+                ChangeKind::InterfaceRemoved,
+                "interface removed".to_string(),
+            ),
+        }
+    }
+    for new_iface in new_interfaces {
+        let name = new_iface.name();
+        if !old.iter().any(|(_, iface)| iface.name() == name) {
+            changes
+                .interface(&name)
+                .compatible(ChangeKind::InterfaceAdded, "interface added".to_string());
+        }
+    }
+    changes.0
+}
+
+fn diff_interface(old: &Interface, new: &Interface, changes: &mut InterfaceChanges) {
+    diff_deprecation(old.annotations(), new.annotations(), changes);
+
+    for old_method in old.methods() {
+        let name = old_method.name();
+        let mut changes = changes.member(&name);
+        let Some(new_method) = new.methods().iter().find(|m| m.name() == name) else {
+            changes.breaking(ChangeKind::MethodRemoved, "method removed".to_string());
+            continue;
+        };
+        let (old_in, old_out) = split_args(old_method.args());
+        let (new_in, new_out) = split_args(new_method.args());
+        diff_args(
+            &old_in,
+            &new_in,
+            "in-args",
+            ChangeKind::MethodInArgsChanged,
+            &mut changes,
+        );
+        diff_args(
+            &old_out,
+            &new_out,
+            "out-args",
+            ChangeKind::MethodOutArgsChanged,
+            &mut changes,
+        );
+        diff_deprecation(
+            old_method.annotations(),
+            new_method.annotations(),
+            &mut changes,
+        );
+    }
+    for new_method in new.methods() {
+        let name = new_method.name();
+        if !old.methods().iter().any(|m| m.name() == name) {
+            changes
+                .member(&name)
+                .compatible(ChangeKind::MethodAdded, "method added".to_string());
+        }
+    }
+
+    for old_signal in old.signals() {
+        let name = old_signal.name();
+        let mut changes = changes.member(&name);
+        let Some(new_signal) = new.signals().iter().find(|s| s.name() == name) else {
+            changes.breaking(ChangeKind::SignalRemoved, "signal removed".to_string());
+            continue;
+        };
+        let old_args: Vec<_> = old_signal.args().iter().collect();
+        let new_args: Vec<_> = new_signal.args().iter().collect();
+        diff_args(
+            &old_args,
+            &new_args,
+            "args",
+            ChangeKind::SignalArgsChanged,
+            &mut changes,
+        );
+        diff_deprecation(
+            old_signal.annotations(),
+            new_signal.annotations(),
+            &mut changes,
+        );
+    }
+    for new_signal in new.signals() {
+        let name = new_signal.name();
+        if !old.signals().iter().any(|s| s.name() == name) {
+            changes
+                .member(&name)
+                .compatible(ChangeKind::SignalAdded, "signal added".to_string());
+        }
+    }
+
+    for old_prop in old.properties() {
+        let name = old_prop.name();
+        let mut changes = changes.member(&name);
+        let Some(new_prop) = new.properties().iter().find(|p| p.name() == name) else {
+            changes.breaking(ChangeKind::PropertyRemoved, "property removed".to_string());
+            continue;
+        };
+        let (old_ty, new_ty) = (old_prop.ty().to_string(), new_prop.ty().to_string());
+        if old_ty != new_ty {
+            changes.breaking(
+                ChangeKind::PropertyTypeChanged,
+                format!("type changed from {} to {}", old_ty, new_ty),
+            );
+        }
+        let (old_access, new_access) = (old_prop.access(), new_prop.access());
+        if old_access != new_access {
+            let narrowed = old_access.read() && !new_access.read()
+                || old_access.write() && !new_access.write();
+            let message = format!(
+                "access changed from {} to {}",
+                access_name(old_access),
+                access_name(new_access)
+            );
+            changes.push(narrowed, ChangeKind::PropertyAccessChanged, message);
+        }
+        let (old_emits, new_emits) = (
+            emits_changed(old_prop.annotations()),
+            emits_changed(new_prop.annotations()),
+        );
+        if old_emits != new_emits {
+            // Clients may rely on the old notifications, unless the value
+            // never changes at all now.
+            let weakened = emits_rank(new_emits) < emits_rank(old_emits) && new_emits != "const";
+            changes.push(
+                weakened,
+                ChangeKind::PropertyEmitsChanged,
+                format!(
+                    "EmitsChangedSignal changed from {} to {}",
+                    old_emits, new_emits
+                ),
+            );
+        }
+        diff_deprecation(old_prop.annotations(), new_prop.annotations(), &mut changes);
+    }
+    for new_prop in new.properties() {
+        let name = new_prop.name();
+        if !old.properties().iter().any(|p| p.name() == name) {
+            changes
+                .member(&name)
+                .compatible(ChangeKind::PropertyAdded, "property added".to_string());
+        }
+    }
+}
+
+/// Compares argument lists, which are breaking to change in type or number
+/// since D-Bus has no optional arguments, but not in name.
+fn diff_args(
+    old: &[&Arg],
+    new: &[&Arg],
+    what: &str,
+    kind: ChangeKind,
+    changes: &mut InterfaceChanges,
+) {
+    let (old_sig, new_sig) = (signature(old), signature(new));
+    if old_sig != new_sig {
+        let message = if new.len() > old.len() && signature(&new[..old.len()]) == old_sig {
+            let added: Vec<_> = new[old.len()..]
+                .iter()
+                .map(|arg| describe_arg(arg))
+                .collect();
+            format!("{} added: {}", what, added.join(", "))
+        } else {
+            format!("{} changed from {} to {}", what, old_sig, new_sig)
+        };
+        changes.breaking(kind, message);
+        return;
+    }
+    let renamed: Vec<_> = old
+        .iter()
+        .zip(new)
+        .filter(|(old, new)| old.name() != new.name())
+        .map(|(old, new)| {
+            format!(
+                "{} to {}",
+                old.name().unwrap_or("_"),
+                new.name().unwrap_or("_")
+            )
+        })
+        .collect();
+    if !renamed.is_empty() {
+        changes.compatible(
+            ChangeKind::ArgsRenamed,
+            format!("{} renamed: {}", what, renamed.join(", ")),
+        );
+    }
+}
+
+fn diff_deprecation(old: &[Annotation], new: &[Annotation], changes: &mut InterfaceChanges) {
+    let deprecated = |annotations: &[Annotation]| {
+        annotations
+            .iter()
+            .any(|a| a.name() == "org.freedesktop.DBus.Deprecated" && a.value() == "true")
+    };
+    match (deprecated(old), deprecated(new)) {
+        (false, true) => {
+            changes.compatible(ChangeKind::DeprecationChanged, "deprecated".to_string())
+        }
+        (true, false) => changes.compatible(
+            ChangeKind::DeprecationChanged,
+            "no longer deprecated".to_string(),
+        ),
+        _ => {}
+    }
+}
+
+fn split_args(args: &[Arg]) -> (Vec<&Arg>, Vec<&Arg>) {
+    args.iter()
+        .partition(|arg| arg.direction() != Some(ArgDirection::Out))
+}
+
+fn signature(args: &[&Arg]) -> String {
+    let types: String = args.iter().map(|arg| arg.ty().to_string()).collect();
+    format!("({})", types)
+}
+
+fn describe_arg(arg: &Arg) -> String {
+    match arg.name() {
+        Some(name) => format!("{}: {}", name, arg.ty().to_string()),
+        None => arg.ty().to_string(),
+    }
+}
+
+fn access_name(access: PropertyAccess) -> &'static str {
+    match access {
+        PropertyAccess::Read => "read",
+        PropertyAccess::Write => "write",
+        PropertyAccess::ReadWrite => "readwrite",
+    }
+}
+
+/// The `EmitsChangedSignal` value, `true` by default.
+fn emits_changed(annotations: &[Annotation]) -> &str {
+    annotations
+        .iter()
+        .find(|a| a.name() == "org.freedesktop.DBus.Property.EmitsChangedSignal")
+        .map_or("true", |a| a.value())
+}
+
+fn emits_rank(value: &str) -> u8 {
+    match value {
+        "true" => 3,
+        "invalidates" => 2,
+        "const" => 1,
+        _ => 0,
+    }
+}
+
+#[derive(Default)]
+struct Changes(Vec<Change>);
+
+impl Changes {
+    fn interface(&mut self, interface: &str) -> InterfaceChanges<'_> {
+        InterfaceChanges {
+            changes: &mut self.0,
+            interface: interface.to_string(),
+            member: None,
+        }
+    }
+}
+
+/// Records changes to an interface, or one of its members.
+struct InterfaceChanges<'c> {
+    changes: &'c mut Vec<Change>,
+    interface: String,
+    member: Option<String>,
+}
+
+impl InterfaceChanges<'_> {
+    fn member(&mut self, member: &str) -> InterfaceChanges<'_> {
+        InterfaceChanges {
+            changes: self.changes,
+            interface: self.interface.clone(),
+            member: Some(member.to_string()),
+        }
+    }
+
+    fn push(&mut self, breaking: bool, kind: ChangeKind, message: String) {
+        self.changes.push(Change {
+            compatibility: if breaking {
+                Compatibility::Breaking
+            } else {
+                Compatibility::Compatible
+            },
+            kind,
+            interface: self.interface.clone(),
+            member: self.member.clone(),
+            message,
+        });
+    }
+
+    fn breaking(&mut self, kind: ChangeKind, message: String) {
+        self.push(true, kind, message);
+    }
+
+    fn compatible(&mut self, kind: ChangeKind, message: String) {
+        self.push(false, kind, message);
+    }
+}
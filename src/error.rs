@@ -0,0 +1,90 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Errors from reading introspection XML files and writing generated code.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A file is not valid D-Bus introspection XML.
+    Xml {
+        path: PathBuf,
+        source: zbus_xml::Error,
+    },
+    /// A glob pattern among the inputs matches no files.
+    NoMatches { pattern: PathBuf },
+    /// An interface is defined differently in two input files.
+    Conflict {
+        interface: String,
+        paths: [PathBuf; 2],
+    },
+    /// Two inputs would be generated into the same output file.
+    OutputCollision { file: String, inputs: [PathBuf; 2] },
+    /// A D-Bus call for introspecting a live service failed.
+    #[cfg(feature = "bus")]
+    Bus { object: String, source: zbus::Error },
+    /// No output directory was given and `OUT_DIR` is not set.
+    NoOutDir,
+    /// A configuration file is malformed or inconsistent.
+    Config { path: PathBuf, message: String },
+    /// Generated code for a file is not valid Rust.
+    InvalidCode { path: PathBuf, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Xml { path, source } => {
+                write!(f, "{}: invalid D-Bus XML: {}", path.display(), source)
+            }
+            Error::NoMatches { pattern } => {
+                write!(f, "{}: pattern matches no files", pattern.display())
+            }
+            Error::Conflict { interface, paths } => write!(
+                f,
+                "interface '{}' is defined differently in {} and {}",
+                interface,
+                paths[0].display(),
+                paths[1].display()
+            ),
+            Error::OutputCollision { file, inputs } => write!(
+                f,
+                "{} and {} would both be generated into {}; rename one or combine them",
+                inputs[0].display(),
+                inputs[1].display(),
+                file
+            ),
+            #[cfg(feature = "bus")]
+            Error::Bus { object, source } => write!(f, "{}: D-Bus call failed: {}", object, source),
+            Error::NoOutDir => write!(f, "OUT_DIR is not set; is this a build script?"),
+            Error::Config { path, message } => write!(f, "{}: {}", path.display(), message),
+            Error::InvalidCode { path, message } => write!(
+                f,
+                "{}: generated code is not valid Rust: {}",
+                path.display(),
+                message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Xml { source, .. } => Some(source),
+            #[cfg(feature = "bus")]
+            Error::Bus { source, .. } => Some(source),
+            Error::NoMatches { .. }
+            | Error::Conflict { .. }
+            | Error::OutputCollision { .. }
+            | Error::NoOutDir
+            | Error::Config { .. }
+            | Error::InvalidCode { .. } => None,
+        }
+    }
+}
@@ -1,4 +1,29 @@
 #![doc = include_str!("../README.md")]
 
+mod builder;
+#[cfg(feature = "bus")]
+mod bus;
 mod codegen;
+#[cfg(feature = "config")]
+mod config;
+mod diff;
+mod error;
+mod lint;
+#[cfg(feature = "reverse")]
+mod reverse;
+mod sources;
+mod xml;
+mod xml_format;
+pub use builder::*;
+#[cfg(feature = "bus")]
+pub use bus::*;
 pub use codegen::*;
+#[cfg(feature = "config")]
+pub use config::*;
+pub use diff::*;
+pub use error::*;
+pub use lint::*;
+#[cfg(feature = "reverse")]
+pub use reverse::*;
+pub use sources::*;
+pub use xml_format::*;
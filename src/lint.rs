@@ -0,0 +1,519 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use zbus_xml::Node;
+
+use crate::codegen::{escape_rust_keyword, to_snake_case};
+use crate::xml::{parse, Element};
+
+/// Annotations in the `org.freedesktop.DBus` namespace that tools know about.
+const KNOWN_ANNOTATIONS: &[&str] = &[
+    "org.freedesktop.DBus.Deprecated",
+    "org.freedesktop.DBus.GLib.Async",
+    "org.freedesktop.DBus.GLib.CSymbol",
+    "org.freedesktop.DBus.GLib.Const",
+    "org.freedesktop.DBus.GLib.ReturnVal",
+    "org.freedesktop.DBus.Method.NoReply",
+    "org.freedesktop.DBus.Property.EmitsChangedSignal",
+];
+
+/// A convention or validity check of [`lint_xml`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rule {
+    /// Not well-formed XML, or not D-Bus introspection data `zbus_xml`
+    /// accepts.
+    InvalidXml,
+    /// Interface, member or property names breaking the D-Bus naming rules.
+    InvalidName,
+    /// Methods, signals and properties not named in PascalCase.
+    MemberCase,
+    /// Arguments without a `name`.
+    MissingArgName,
+    /// Method arguments without a `direction`.
+    MissingDirection,
+    /// Signal arguments with a `direction`.
+    SignalDirection,
+    /// Unknown or misspelled `org.freedesktop.DBus.*` annotations.
+    UnknownAnnotation,
+    /// Names that become Rust keywords in generated code.
+    KeywordName,
+    /// Names that become the same Rust name in generated code.
+    NameCollision,
+}
+
+impl Rule {
+    pub const ALL: &'static [Rule] = &[
+        Rule::InvalidXml,
+        Rule::InvalidName,
+        Rule::MemberCase,
+        Rule::MissingArgName,
+        Rule::MissingDirection,
+        Rule::SignalDirection,
+        Rule::UnknownAnnotation,
+        Rule::KeywordName,
+        Rule::NameCollision,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::InvalidXml => "invalid-xml",
+            Rule::InvalidName => "invalid-name",
+            Rule::MemberCase => "member-case",
+            Rule::MissingArgName => "missing-arg-name",
+            Rule::MissingDirection => "missing-direction",
+            Rule::SignalDirection => "signal-direction",
+            Rule::UnknownAnnotation => "unknown-annotation",
+            Rule::KeywordName => "keyword-name",
+            Rule::NameCollision => "name-collision",
+        }
+    }
+
+    /// Invalid data is denied, convention problems are warned about.
+    pub fn default_level(self) -> Level {
+        match self {
+            Rule::InvalidXml | Rule::InvalidName => Level::Deny,
+            _ => Level::Warn,
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.name() == s)
+            .ok_or_else(|| format!("unknown lint rule '{}'", s))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How a [`Rule`]'s findings are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Not reported.
+    Allow,
+    /// Reported as a warning.
+    Warn,
+    /// Reported as an error.
+    Deny,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Allow => "allowed",
+            Level::Warn => "warning",
+            Level::Deny => "error",
+        })
+    }
+}
+
+/// The [`Level`] of every rule: its default unless set otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels {
+    levels: BTreeMap<Rule, Level>,
+}
+
+impl LintLevels {
+    /// Sets the level of the rule named `rule`, or of every rule for `all`.
+    pub fn set(&mut self, rule: &str, level: Level) -> Result<(), String> {
+        if rule == "all" {
+            for rule in Rule::ALL {
+                self.levels.insert(*rule, level);
+            }
+        } else {
+            self.levels.insert(rule.parse()?, level);
+        }
+        Ok(())
+    }
+
+    pub fn level(&self, rule: Rule) -> Level {
+        self.levels
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_level())
+    }
+}
+
+/// A problem found by [`lint_xml`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub level: Level,
+    /// 1-based line, if known.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.level, self.rule, self.message)
+    }
+}
+
+/// Checks introspection XML for invalid data and problems that only show up
+/// in generated code, in document order. Findings of allowed rules are left
+/// out.
+pub fn lint_xml(xml: &str, levels: &LintLevels) -> Vec<Diagnostic> {
+    let mut lint = Lint {
+        levels,
+        diagnostics: Vec::new(),
+        found_invalid: false,
+        interfaces: Vec::new(),
+    };
+    match parse(xml) {
+        Err(err) => lint.report(Rule::InvalidXml, err.line, err.message),
+        Ok(root) if root.name != "node" => lint.report(
+            Rule::InvalidXml,
+            root.line,
+            format!("root element is <{}>, not <node>", root.name),
+        ),
+        Ok(root) => {
+            lint.node(&root);
+            // Anything else `zbus_xml` rejects, such as invalid signatures.
+            if !lint.found_invalid {
+                if let Err(err) = Node::from_reader(xml.as_bytes()) {
+                    lint.report_at(Rule::InvalidXml, None, err.to_string());
+                }
+            }
+        }
+    }
+    lint.diagnostics.sort_by_key(|d| d.line);
+    lint.diagnostics
+}
+
+struct Lint<'l> {
+    levels: &'l LintLevels,
+    diagnostics: Vec<Diagnostic>,
+    /// Whether data `zbus_xml` rejects was found, allowed or not.
+    found_invalid: bool,
+    /// Rust and D-Bus names of the interfaces seen so far.
+    interfaces: Vec<(String, String)>,
+}
+
+impl Lint<'_> {
+    fn report(&mut self, rule: Rule, line: usize, message: String) {
+        self.report_at(rule, Some(line), message);
+    }
+
+    fn report_at(&mut self, rule: Rule, line: Option<usize>, message: String) {
+        self.found_invalid |= matches!(rule, Rule::InvalidXml | Rule::InvalidName);
+        let level = self.levels.level(rule);
+        if level != Level::Allow {
+            self.diagnostics.push(Diagnostic {
+                rule,
+                level,
+                line,
+                message,
+            });
+        }
+    }
+
+    fn node(&mut self, node: &Element) {
+        for iface in node.children_named("interface") {
+            let Some(name) = iface.attribute("name") else {
+                self.report(
+                    Rule::InvalidName,
+                    iface.line,
+                    "interface has no name".into(),
+                );
+                continue;
+            };
+            if !is_interface_name(name) {
+                self.report(
+                    Rule::InvalidName,
+                    iface.line,
+                    format!("'{}' is not a valid interface name", name),
+                );
+            }
+            let rust_name = name.rsplit('.').next().unwrap_or(name).to_string();
+            let other = self
+                .interfaces
+                .iter()
+                .find(|(seen, other)| *seen == rust_name && other != name)
+                .map(|(_, other)| other.clone());
+            if let Some(other) = other {
+                self.report(
+                    Rule::NameCollision,
+                    iface.line,
+                    format!(
+                        "interfaces '{}' and '{}' are both named `{}` in Rust",
+                        other, name, rust_name
+                    ),
+                );
+            }
+            self.interfaces.push((rust_name, name.to_string()));
+            self.interface(iface, name);
+        }
+        for child in node.children_named("node") {
+            self.node(child);
+        }
+    }
+
+    fn interface(&mut self, iface: &Element, iface_name: &str) {
+        self.annotations(iface);
+        let mut rust_names = HashSet::new();
+        for member in iface.children() {
+            let kind = match member.name.as_str() {
+                "method" | "signal" | "property" => member.name.as_str(),
+                _ => continue,
+            };
+            let Some(name) = member.attribute("name") else {
+                self.report(
+                    Rule::InvalidName,
+                    member.line,
+                    format!("{} has no name", kind),
+                );
+                continue;
+            };
+            let what = format!("{} '{}.{}'", kind, iface_name, name);
+            if !is_member_name(name) {
+                self.report(
+                    Rule::InvalidName,
+                    member.line,
+                    format!("{} has an invalid name", what),
+                );
+                continue;
+            }
+            if !is_pascal_case(name) {
+                self.report(
+                    Rule::MemberCase,
+                    member.line,
+                    format!("{} is not PascalCase", what),
+                );
+            }
+            let snake = to_snake_case(name);
+            if escape_rust_keyword(&snake) != snake {
+                self.report(
+                    Rule::KeywordName,
+                    member.line,
+                    format!("{} becomes the Rust keyword `{}`", what, snake),
+                );
+            }
+            let mut member_names = vec![snake.clone()];
+            if kind == "property"
+                && member
+                    .attribute("access")
+                    .is_some_and(|a| a.contains("write"))
+            {
+                member_names.push(format!("set_{}", snake));
+            }
+            for rust_name in member_names {
+                if !rust_names.insert(rust_name.clone()) {
+                    self.report(
+                        Rule::NameCollision,
+                        member.line,
+                        format!("{} collides with another member as `{}`", what, rust_name),
+                    );
+                }
+            }
+
+            if kind == "property" {
+                self.property(member, &what);
+            } else {
+                self.args(member, kind == "signal", &what);
+            }
+            self.annotations(member);
+        }
+    }
+
+    fn property(&mut self, property: &Element, what: &str) {
+        if property.attribute("type").is_none() {
+            self.report(
+                Rule::InvalidXml,
+                property.line,
+                format!("{} has no type", what),
+            );
+        }
+        match property.attribute("access") {
+            Some("read" | "write" | "readwrite") => {}
+            Some(access) => self.report(
+                Rule::InvalidXml,
+                property.line,
+                format!("{} has invalid access '{}'", what, access),
+            ),
+            None => self.report(
+                Rule::InvalidXml,
+                property.line,
+                format!("{} has no access", what),
+            ),
+        }
+    }
+
+    fn args(&mut self, member: &Element, signal: bool, what: &str) {
+        let mut arg_names = HashSet::new();
+        for (idx, arg) in member.children_named("arg").enumerate() {
+            let direction = arg.attribute("direction");
+            let arg_what = match arg.attribute("name") {
+                Some(name) => format!("argument '{}' of {}", name, what),
+                None => format!("argument {} of {}", idx, what),
+            };
+            if arg.attribute("type").is_none() {
+                self.report(
+                    Rule::InvalidXml,
+                    arg.line,
+                    format!("{} has no type", arg_what),
+                );
+            }
+            match (signal, direction) {
+                (_, Some(d)) if d != "in" && d != "out" => self.report(
+                    Rule::InvalidXml,
+                    arg.line,
+                    format!("{} has invalid direction '{}'", arg_what, d),
+                ),
+                (true, Some(_)) => self.report(
+                    Rule::SignalDirection,
+                    arg.line,
+                    format!("{} has a direction", arg_what),
+                ),
+                (false, None) => self.report(
+                    Rule::MissingDirection,
+                    arg.line,
+                    format!("{} has no direction (taken as \"in\")", arg_what),
+                ),
+                _ => {}
+            }
+
+            match arg.attribute("name") {
+                None => self.report(
+                    Rule::MissingArgName,
+                    arg.line,
+                    format!("{} has no name", arg_what),
+                ),
+                Some(name) => {
+                    let snake = to_snake_case(name);
+                    if escape_rust_keyword(&snake) != snake {
+                        self.report(
+                            Rule::KeywordName,
+                            arg.line,
+                            format!("{} becomes the Rust keyword `{}`", arg_what, snake),
+                        );
+                    }
+                    let input = signal || direction != Some("out");
+                    if input && !arg_names.insert(snake.clone()) {
+                        self.report(
+                            Rule::NameCollision,
+                            arg.line,
+                            format!("{} collides with another argument as `{}`", arg_what, snake),
+                        );
+                    }
+                }
+            }
+            self.annotations(arg);
+        }
+    }
+
+    fn annotations(&mut self, element: &Element) {
+        for annotation in element.children_named("annotation") {
+            let Some(name) = annotation.attribute("name") else {
+                continue;
+            };
+            let dbus_namespace = name
+                .to_ascii_lowercase()
+                .starts_with("org.freedesktop.dbus.");
+            if !dbus_namespace || KNOWN_ANNOTATIONS.contains(&name) {
+                continue;
+            }
+            let suggestion = KNOWN_ANNOTATIONS
+                .iter()
+                .map(|known| {
+                    (
+                        edit_distance(&name.to_ascii_lowercase(), &known.to_ascii_lowercase()),
+                        known,
+                    )
+                })
+                .filter(|(distance, _)| *distance <= 3)
+                .min();
+            let message = match suggestion {
+                Some((_, known)) => {
+                    format!("unknown annotation '{}'; did you mean '{}'?", name, known)
+                }
+                None => format!("unknown annotation '{}'", name),
+            };
+            self.report(Rule::UnknownAnnotation, annotation.line, message);
+        }
+    }
+}
+
+/// Two or more dot-separated elements of `[A-Za-z_][A-Za-z0-9_]*`, at most
+/// 255 characters.
+fn is_interface_name(name: &str) -> bool {
+    let elements: Vec<_> = name.split('.').collect();
+    name.len() <= 255 && elements.len() >= 2 && elements.iter().all(|e| is_member_name(e))
+}
+
+/// `[A-Za-z_][A-Za-z0-9_]*`, at most 255 characters.
+fn is_member_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len() <= 255
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase()) && !name.contains('_')
+}
+
+/// Levenshtein distance.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut row: Vec<_> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert!(is_interface_name("org.example.Foo1"));
+        assert!(!is_interface_name("Foo"));
+        assert!(!is_interface_name("org..Foo"));
+        assert!(!is_interface_name("org.1example.Foo"));
+        assert!(is_member_name("GetAll"));
+        assert!(!is_member_name("Get-All"));
+        assert!(is_pascal_case("GetAll"));
+        assert!(!is_pascal_case("getAll"));
+        assert!(!is_pascal_case("Get_All"));
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn levels() {
+        let mut levels = LintLevels::default();
+        assert_eq!(levels.level(Rule::InvalidName), Level::Deny);
+        assert_eq!(levels.level(Rule::MemberCase), Level::Warn);
+        levels.set("all", Level::Allow).unwrap();
+        levels.set("member-case", Level::Deny).unwrap();
+        assert_eq!(levels.level(Rule::InvalidName), Level::Allow);
+        assert_eq!(levels.level(Rule::MemberCase), Level::Deny);
+        assert!(levels.set("member_case", Level::Deny).is_err());
+    }
+}
@@ -0,0 +1,593 @@
+use quick_xml::escape::escape;
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, Expr, ExprLit, FnArg, GenericArgument, ImplItem, Item, Lit, LitStr, Meta, Pat,
+    PathArguments, ReturnType, Signature, Token, TraitItem, Type,
+};
+
+use crate::codegen::{escape_rust_keyword, to_pascal_case};
+use crate::{format_xml, Options, XmlFormat};
+
+/// Introspection XML for the `#[proxy]` traits and `#[interface]` impls in
+/// Rust `code`, including those in inline modules.
+///
+/// Members are named like zbus names them, unless `#[zbus(name = "...")]`
+/// says otherwise; `property`, `signal`, `out_args` and `no_reply` are
+/// honored. A tuple return type is several out-args unless `out_args` names
+/// just one. Rust types map back to D-Bus signatures as the inverse of
+/// [`dbus_type_to_rust`](crate::dbus_type_to_rust), with
+/// [`Options::type_overrides`] resolving named types; other types are an
+/// error.
+pub fn generate_xml_from_rust(code: &str, options: &Options) -> Result<String, syn::Error> {
+    let file = syn::parse_file(code)?;
+    let mut interfaces = Vec::new();
+    collect_interfaces(&file.items, options, &mut interfaces)?;
+
+    let mut xml = String::from("<node>\n");
+    for iface in &interfaces {
+        iface.write(&mut xml);
+    }
+    xml.push_str("</node>\n");
+    Ok(format_xml(&xml, &XmlFormat::default()).expect("generated XML is valid"))
+}
+
+/// An interface read from Rust code.
+struct Interface {
+    name: String,
+    members: Vec<Member>,
+}
+
+enum Member {
+    Method {
+        name: String,
+        args: Vec<Arg>,
+        annotations: Vec<(&'static str, String)>,
+    },
+    Signal {
+        name: String,
+        args: Vec<Arg>,
+    },
+    Property {
+        name: String,
+        ty: String,
+        read: bool,
+        write: bool,
+        annotations: Vec<(&'static str, String)>,
+    },
+}
+
+struct Arg {
+    name: Option<String>,
+    ty: String,
+    direction: Option<&'static str>,
+}
+
+/// The members of a `#[zbus(...)]` attribute that matter here.
+#[derive(Default)]
+struct MemberAttrs {
+    name: Option<String>,
+    property: bool,
+    emits_changed_signal: Option<String>,
+    signal: bool,
+    out_args: Vec<String>,
+    no_reply: bool,
+}
+
+fn collect_interfaces(
+    items: &[Item],
+    options: &Options,
+    out: &mut Vec<Interface>,
+) -> Result<(), syn::Error> {
+    for item in items {
+        let iface = match item {
+            Item::Trait(item) => match interface_name(&item.attrs, &["proxy", "dbus_proxy"])? {
+                Some(name) => {
+                    let name = name.unwrap_or_else(|| format!("org.freedesktop.{}", item.ident));
+                    let mut iface = Interface {
+                        name,
+                        members: Vec::new(),
+                    };
+                    for trait_item in &item.items {
+                        if let TraitItem::Fn(function) = trait_item {
+                            iface.add(&function.attrs, &function.sig, false, options)?;
+                        }
+                    }
+                    iface
+                }
+                None => continue,
+            },
+            Item::Impl(item) => {
+                match interface_name(&item.attrs, &["interface", "dbus_interface"])? {
+                    Some(name) => {
+                        let name = name.unwrap_or_else(|| {
+                            format!(
+                                "org.freedesktop.{}",
+                                item.self_ty.to_token_stream().to_string().replace(' ', "")
+                            )
+                        });
+                        let mut iface = Interface {
+                            name,
+                            members: Vec::new(),
+                        };
+                        for impl_item in &item.items {
+                            match impl_item {
+                                ImplItem::Fn(function) => {
+                                    iface.add(&function.attrs, &function.sig, true, options)?
+                                }
+                                // Bodiless signal declarations.
+                                ImplItem::Verbatim(tokens) => {
+                                    let function: syn::TraitItemFn = syn::parse2(tokens.clone())?;
+                                    iface.add(&function.attrs, &function.sig, true, options)?
+                                }
+                                _ => {}
+                            }
+                        }
+                        iface
+                    }
+                    None => continue,
+                }
+            }
+            Item::Mod(item) => {
+                if let Some((_, items)) = &item.content {
+                    collect_interfaces(items, options, out)?;
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if !out.iter().any(|seen| seen.name == iface.name) {
+            out.push(iface);
+        }
+    }
+    Ok(())
+}
+
+/// The interface name of an item with one of the `macros` as attribute:
+/// `Some(None)` if the attribute does not name one.
+fn interface_name(
+    attrs: &[Attribute],
+    macros: &[&str],
+) -> Result<Option<Option<String>>, syn::Error> {
+    let attr = match attrs
+        .iter()
+        .find(|attr| macros.iter().any(|name| last_segment_is(attr, name)))
+    {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    for meta in metas(attr)? {
+        if let Meta::NameValue(meta) = &meta {
+            if meta.path.is_ident("interface") || meta.path.is_ident("name") {
+                return Ok(Some(Some(string_value(&meta.value)?)));
+            }
+        }
+    }
+    Ok(Some(None))
+}
+
+impl Interface {
+    /// Adds the member declared by a function, with `server` telling an
+    /// `#[interface]` impl from a `#[proxy]` trait.
+    fn add(
+        &mut self,
+        attrs: &[Attribute],
+        sig: &Signature,
+        server: bool,
+        options: &Options,
+    ) -> Result<(), syn::Error> {
+        let attrs = member_attrs(attrs)?;
+        let rust_name = unescape(&sig.ident.to_string());
+        let mut inputs = Vec::new();
+        for input in &sig.inputs {
+            let input = match input {
+                FnArg::Typed(input) => input,
+                FnArg::Receiver(_) => continue,
+            };
+            // Context parameters filled in by zbus.
+            if input.attrs.iter().any(|attr| last_segment_is(attr, "zbus")) {
+                continue;
+            }
+            if server && attrs.signal && inputs.is_empty() && is_emitter(&input.ty) {
+                continue;
+            }
+            let name = match &*input.pat {
+                Pat::Ident(pat) => Some(unescape(&pat.ident.to_string())),
+                _ => None,
+            };
+            inputs.push((name, signature(&input.ty, options)?));
+        }
+        let output = match &sig.output {
+            ReturnType::Default => None,
+            ReturnType::Type(_, ty) => Some(unwrap_result(ty)),
+        };
+
+        if attrs.property {
+            let (base, write) = match rust_name.strip_prefix("set_") {
+                Some(base) => (base.to_string(), true),
+                None => (rust_name.clone(), false),
+            };
+            let name = attrs.name.unwrap_or_else(|| to_pascal_case(&base));
+            let ty = if write {
+                match inputs.into_iter().next() {
+                    Some((_, ty)) => ty,
+                    None => {
+                        return Err(syn::Error::new_spanned(
+                            sig,
+                            "property setter without value",
+                        ))
+                    }
+                }
+            } else {
+                match output {
+                    Some(ty) => signature(ty, options)?,
+                    None => {
+                        return Err(syn::Error::new_spanned(sig, "property getter without type"))
+                    }
+                }
+            };
+            let mut annotations = Vec::new();
+            if let Some(value) = attrs.emits_changed_signal {
+                annotations.push(("org.freedesktop.DBus.Property.EmitsChangedSignal", value));
+            }
+            return self.add_property(name, ty, write, annotations);
+        }
+
+        let name = attrs.name.unwrap_or_else(|| to_pascal_case(&rust_name));
+        if attrs.signal {
+            let mut args: Vec<Arg> = inputs
+                .into_iter()
+                .map(|(name, ty)| Arg {
+                    name,
+                    ty,
+                    direction: None,
+                })
+                .collect();
+            // Generated proxies declare signals as `fn x(&self) -> Result<SignalStream<T>>`.
+            if let (true, Some(Type::Path(path))) = (args.is_empty(), output) {
+                let last = path.path.segments.last().expect("paths are not empty");
+                if last.ident == "SignalStream" {
+                    if let Some(ty) = type_args(&last.arguments).next() {
+                        args = out_types(ty, 0)
+                            .into_iter()
+                            .map(|ty| signature(ty, options))
+                            .collect::<Result<Vec<_>, _>>()?
+                            .into_iter()
+                            .map(|ty| Arg {
+                                name: None,
+                                ty,
+                                direction: None,
+                            })
+                            .collect();
+                    }
+                }
+            }
+            self.members.push(Member::Signal { name, args });
+            return Ok(());
+        }
+
+        let mut args: Vec<Arg> = inputs
+            .into_iter()
+            .map(|(name, ty)| Arg {
+                name,
+                ty,
+                direction: Some("in"),
+            })
+            .collect();
+        if let Some(ty) = output {
+            for (i, ty) in out_types(ty, attrs.out_args.len()).into_iter().enumerate() {
+                args.push(Arg {
+                    name: attrs.out_args.get(i).cloned(),
+                    ty: signature(ty, options)?,
+                    direction: Some("out"),
+                });
+            }
+        }
+        let mut annotations = Vec::new();
+        if attrs.no_reply {
+            annotations.push(("org.freedesktop.DBus.Method.NoReply", "true".to_string()));
+        }
+        self.members.push(Member::Method {
+            name,
+            args,
+            annotations,
+        });
+        Ok(())
+    }
+
+    /// Adds a property getter or setter, merging it with its counterpart.
+    fn add_property(
+        &mut self,
+        name: String,
+        ty: String,
+        write: bool,
+        annotations: Vec<(&'static str, String)>,
+    ) -> Result<(), syn::Error> {
+        for member in &mut self.members {
+            if let Member::Property {
+                name: seen,
+                read: seen_read,
+                write: seen_write,
+                annotations: seen_annotations,
+                ..
+            } = member
+            {
+                if *seen == name {
+                    *seen_read |= !write;
+                    *seen_write |= write;
+                    seen_annotations.extend(annotations);
+                    return Ok(());
+                }
+            }
+        }
+        self.members.push(Member::Property {
+            name,
+            ty,
+            read: !write,
+            write,
+            annotations,
+        });
+        Ok(())
+    }
+
+    fn write(&self, xml: &mut String) {
+        xml.push_str(&format!("<interface name=\"{}\">\n", escape(&self.name)));
+        for member in &self.members {
+            match member {
+                Member::Method {
+                    name,
+                    args,
+                    annotations,
+                } => {
+                    xml.push_str(&format!("<method name=\"{}\">\n", escape(name)));
+                    write_args(xml, args);
+                    write_annotations(xml, annotations);
+                    xml.push_str("</method>\n");
+                }
+                Member::Signal { name, args } => {
+                    xml.push_str(&format!("<signal name=\"{}\">\n", escape(name)));
+                    write_args(xml, args);
+                    xml.push_str("</signal>\n");
+                }
+                Member::Property {
+                    name,
+                    ty,
+                    read,
+                    write,
+                    annotations,
+                } => {
+                    let access = match (read, write) {
+                        (true, true) => "readwrite",
+                        (false, true) => "write",
+                        _ => "read",
+                    };
+                    xml.push_str(&format!(
+                        "<property name=\"{}\" type=\"{}\" access=\"{}\">\n",
+                        escape(name),
+                        escape(ty),
+                        access
+                    ));
+                    write_annotations(xml, annotations);
+                    xml.push_str("</property>\n");
+                }
+            }
+        }
+        xml.push_str("</interface>\n");
+    }
+}
+
+fn write_args(xml: &mut String, args: &[Arg]) {
+    for arg in args {
+        xml.push_str("<arg");
+        if let Some(name) = &arg.name {
+            xml.push_str(&format!(" name=\"{}\"", escape(name)));
+        }
+        xml.push_str(&format!(" type=\"{}\"", escape(&arg.ty)));
+        if let Some(direction) = arg.direction {
+            xml.push_str(&format!(" direction=\"{}\"", direction));
+        }
+        xml.push_str("/>\n");
+    }
+}
+
+fn write_annotations(xml: &mut String, annotations: &[(&str, String)]) {
+    for (name, value) in annotations {
+        xml.push_str(&format!(
+            "<annotation name=\"{}\" value=\"{}\"/>\n",
+            name,
+            escape(value)
+        ));
+    }
+}
+
+fn member_attrs(attrs: &[Attribute]) -> Result<MemberAttrs, syn::Error> {
+    let mut out = MemberAttrs::default();
+    let zbus_attrs = attrs.iter().filter(|attr| {
+        ["zbus", "dbus_proxy", "dbus_interface"]
+            .iter()
+            .any(|name| last_segment_is(attr, name))
+    });
+    for attr in zbus_attrs {
+        for meta in metas(attr)? {
+            match &meta {
+                Meta::Path(path) if path.is_ident("property") => out.property = true,
+                Meta::Path(path) if path.is_ident("signal") => out.signal = true,
+                Meta::Path(path) if path.is_ident("no_reply") => out.no_reply = true,
+                Meta::NameValue(meta) if meta.path.is_ident("name") => {
+                    out.name = Some(string_value(&meta.value)?)
+                }
+                Meta::List(list) if list.path.is_ident("property") => {
+                    out.property = true;
+                    let nested =
+                        list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+                    for meta in nested {
+                        if let Meta::NameValue(meta) = meta {
+                            if meta.path.is_ident("emits_changed_signal") {
+                                out.emits_changed_signal = Some(string_value(&meta.value)?);
+                            }
+                        }
+                    }
+                }
+                Meta::List(list) if list.path.is_ident("out_args") => {
+                    let names =
+                        list.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)?;
+                    out.out_args = names.iter().map(LitStr::value).collect();
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// The comma-separated items of an attribute's argument list.
+fn metas(attr: &Attribute) -> Result<Vec<Meta>, syn::Error> {
+    match &attr.meta {
+        Meta::List(list) => Ok(list
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?
+            .into_iter()
+            .collect()),
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn string_value(expr: &Expr) -> Result<String, syn::Error> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit.value()),
+        _ => Err(syn::Error::new_spanned(expr, "expected a string literal")),
+    }
+}
+
+fn last_segment_is(attr: &Attribute, name: &str) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == name)
+}
+
+/// A Rust identifier as the name it stands for, without `r#` or the `_`
+/// appended to keywords.
+fn unescape(ident: &str) -> String {
+    let ident = ident.strip_prefix("r#").unwrap_or(ident);
+    match ident.strip_suffix('_') {
+        Some(name) if escape_rust_keyword(name) == ident => name.to_string(),
+        _ => ident.to_string(),
+    }
+}
+
+fn is_emitter(ty: &Type) -> bool {
+    let ty = match ty {
+        Type::Reference(reference) => &*reference.elem,
+        ty => ty,
+    };
+    match ty {
+        Type::Path(path) => path.path.segments.last().is_some_and(|segment| {
+            segment.ident == "SignalEmitter" || segment.ident == "SignalContext"
+        }),
+        _ => false,
+    }
+}
+
+/// `T` for `Result<T>`, `zbus::fdo::Result<T>` and the like, else `ty`.
+fn unwrap_result(ty: &Type) -> &Type {
+    if let Type::Path(path) = ty {
+        let last = path.path.segments.last().expect("paths are not empty");
+        if last.ident == "Result" {
+            if let Some(ok) = type_args(&last.arguments).next() {
+                return ok;
+            }
+        }
+    }
+    ty
+}
+
+/// The out-arg types of a return type: none for `()`, the fields of a tuple
+/// unless a single out-arg is named, or the type itself.
+fn out_types(ty: &Type, named: usize) -> Vec<&Type> {
+    match ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Vec::new(),
+        Type::Tuple(tuple) if named != 1 => tuple.elems.iter().collect(),
+        Type::Paren(paren) => out_types(&paren.elem, named),
+        ty => vec![ty],
+    }
+}
+
+fn type_args(arguments: &PathArguments) -> impl Iterator<Item = &Type> {
+    let args = match arguments {
+        PathArguments::AngleBracketed(args) => Some(args.args.iter()),
+        _ => None,
+    };
+    args.into_iter().flatten().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+/// The D-Bus signature of a Rust type.
+fn signature(ty: &Type, options: &Options) -> Result<String, syn::Error> {
+    let unsupported = || {
+        syn::Error::new_spanned(
+            ty,
+            format!(
+                "cannot map `{}` to a D-Bus type; add a type override",
+                ty.to_token_stream().to_string().replace(' ', "")
+            ),
+        )
+    };
+    let path = match ty {
+        Type::Reference(reference) => return signature(&reference.elem, options),
+        Type::Paren(paren) => return signature(&paren.elem, options),
+        Type::Group(group) => return signature(&group.elem, options),
+        Type::Slice(slice) => return Ok(format!("a{}", signature(&slice.elem, options)?)),
+        Type::Array(array) => return Ok(format!("a{}", signature(&array.elem, options)?)),
+        Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+            let fields = tuple
+                .elems
+                .iter()
+                .map(|ty| signature(ty, options))
+                .collect::<Result<String, _>>()?;
+            return Ok(format!("({})", fields));
+        }
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return Err(unsupported()),
+    };
+
+    let written = path.to_token_stream().to_string().replace(' ', "");
+    let last = path.segments.last().expect("paths are not empty");
+    for (signature, rust) in &options.type_overrides {
+        let rust = rust.replace(' ', "");
+        if rust == written || last.ident == rust {
+            return Ok(signature.clone());
+        }
+    }
+
+    let mut args = type_args(&last.arguments);
+    let mut arg = || {
+        args.next()
+            .ok_or_else(unsupported)
+            .and_then(|ty| signature(ty, options))
+    };
+    let signature = match last.ident.to_string().as_str() {
+        "u8" => "y".to_string(),
+        "bool" => "b".to_string(),
+        "i16" => "n".to_string(),
+        "u16" => "q".to_string(),
+        "i32" => "i".to_string(),
+        "u32" => "u".to_string(),
+        "i64" => "x".to_string(),
+        "u64" => "t".to_string(),
+        "f64" => "d".to_string(),
+        "String" | "str" | "Str" => "s".to_string(),
+        "ObjectPath" | "OwnedObjectPath" => "o".to_string(),
+        "Signature" | "OwnedSignature" => "g".to_string(),
+        "Value" | "OwnedValue" => "v".to_string(),
+        "Fd" | "OwnedFd" => "h".to_string(),
+        "Vec" | "VecDeque" | "HashSet" | "BTreeSet" | "Array" => format!("a{}", arg()?),
+        "HashMap" | "BTreeMap" => format!("a{{{}{}}}", arg()?, arg()?),
+        "Box" | "Arc" | "Rc" | "Cow" => arg()?,
+        _ => return Err(unsupported()),
+    };
+    Ok(signature)
+}
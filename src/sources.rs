@@ -0,0 +1,349 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use zbus_xml::{Interface, Node};
+
+use crate::codegen::{
+    generate, generate_cli_client, generate_docs, generate_header, generate_modules, interfaces_at,
+};
+use crate::{DocFormat, Error, GeneratedFile, Mode, Options};
+
+/// A parsed introspection XML file, or the introspection data of a live
+/// object tree.
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub(crate) path: PathBuf,
+    pub(crate) xml: String,
+    /// Root nodes with the object path they describe, unless named absolutely.
+    pub(crate) nodes: Vec<(String, Node<'static>)>,
+    /// Interfaces left out of [`Source::registrations`].
+    pub(crate) skipped_interfaces: &'static [&'static str],
+}
+
+impl Source {
+    /// Parses `xml`, read from `path` (only used in messages and headers).
+    pub fn from_xml(path: impl Into<PathBuf>, xml: String) -> Result<Self, Error> {
+        let path = path.into();
+        let node = Node::from_reader(xml.as_bytes()).map_err(|source| Error::Xml {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(Source {
+            path,
+            xml,
+            nodes: vec![("/".to_string(), node)],
+            skipped_interfaces: &[],
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The XML text; for an introspected object tree, that of each object in
+    /// turn.
+    pub fn xml(&self) -> &str {
+        &self.xml
+    }
+
+    /// Every interface of the source with the object path it is found at.
+    pub(crate) fn registrations(&self) -> Vec<(String, &Interface<'static>)> {
+        self.nodes
+            .iter()
+            .flat_map(|(path, node)| interfaces_at(node, path))
+            .filter(|(_, iface)| !self.skipped_interfaces.contains(&iface.name().as_str()))
+            .collect()
+    }
+}
+
+/// Reads and parses XML files, the `*.xml` files of directories, and the
+/// files matching glob patterns, where `*` and `?` in a path component match
+/// any characters and any one character.
+pub fn read_sources(inputs: &[PathBuf]) -> Result<Vec<Source>, Error> {
+    let mut sources = Vec::new();
+    for path in input_files(inputs)? {
+        let xml = fs::read_to_string(&path).map_err(|source| Error::Io {
+            path: path.clone(),
+            source,
+        })?;
+        sources.push(Source::from_xml(path, xml)?);
+    }
+    Ok(sources)
+}
+
+/// The files [`read_sources`] reads for `inputs`, without repeats.
+pub fn input_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let paths = if is_glob(input) {
+            let matches = glob(input)?;
+            if matches.is_empty() {
+                return Err(Error::NoMatches {
+                    pattern: input.clone(),
+                });
+            }
+            matches
+        } else {
+            xml_files(input)?
+        };
+        for path in paths {
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Code for the interfaces of all `sources`, as one file.
+///
+/// An interface may be defined in several sources, as long as the
+/// definitions are identical. Interfaces whose Rust names collide are
+/// renamed after their longer names, with a warning.
+pub fn generate_from_sources(
+    sources: &[Source],
+    mode: Mode,
+    options: &Options,
+) -> Result<String, Error> {
+    generate(&merged_registrations(sources)?, mode, options)
+        .map_err(|err| invalid_code(sources, err))
+}
+
+/// Modules for the interfaces of all `sources`, like
+/// [`generate_modules_from_xml`](crate::generate_modules_from_xml).
+pub fn generate_modules_from_sources(
+    sources: &[Source],
+    mode: Mode,
+    options: &Options,
+) -> Result<Vec<GeneratedFile>, Error> {
+    generate_modules(&merged_registrations(sources)?, mode, options)
+}
+
+/// Documentation for the interfaces of all `sources`, like
+/// [`generate_docs_from_xml`](crate::generate_docs_from_xml).
+pub fn generate_docs_from_sources(
+    sources: &[Source],
+    format: DocFormat,
+    options: &Options,
+) -> Result<String, Error> {
+    Ok(generate_docs(
+        &merged_registrations(sources)?,
+        format,
+        options,
+    ))
+}
+
+/// A command-line client for the interfaces of all `sources`, like
+/// [`generate_cli_client_from_xml`](crate::generate_cli_client_from_xml).
+pub fn generate_cli_client_from_sources(
+    sources: &[Source],
+    options: &Options,
+) -> Result<String, Error> {
+    generate_cli_client(&merged_registrations(sources)?, options)
+        .map_err(|err| invalid_code(sources, err))
+}
+
+/// Generated code for `sources` that is not valid Rust, reported against
+/// the first of them.
+fn invalid_code(sources: &[Source], err: syn::Error) -> Error {
+    Error::InvalidCode {
+        path: sources
+            .first()
+            .map(|source| source.path.clone())
+            .unwrap_or_default(),
+        message: err.to_string(),
+    }
+}
+
+/// The IR of the interfaces of all `sources`, like
+/// [`ir_from_xml`](crate::ir_from_xml).
+#[cfg(feature = "json")]
+pub fn ir_from_sources(sources: &[Source], options: &Options) -> Result<crate::Ir, Error> {
+    Ok(crate::codegen::build_ir(
+        &merged_registrations(sources)?,
+        options,
+    ))
+}
+
+/// [`generate_header`] for code generated from `sources`.
+pub fn generate_sources_header(sources: &[Source], mode: Mode, options: &Options) -> String {
+    let names: Vec<_> = sources
+        .iter()
+        .map(|source| source.path.display().to_string())
+        .collect();
+    let header_sources: Vec<_> = names
+        .iter()
+        .zip(sources)
+        .map(|(name, source)| (name.as_str(), source.xml.as_str()))
+        .collect();
+    generate_header(&header_sources, mode, options)
+}
+
+/// The registrations of all `sources`, checking that interfaces defined more
+/// than once are defined the same way everywhere.
+pub(crate) fn merged_registrations(
+    sources: &[Source],
+) -> Result<Vec<(String, &Interface<'static>)>, Error> {
+    let mut registrations = Vec::new();
+    let mut definitions: Vec<(&Path, &Interface)> = Vec::new();
+    for source in sources {
+        for (path, iface) in source.registrations() {
+            match definitions
+                .iter()
+                .find(|(_, seen)| seen.name() == iface.name())
+            {
+                Some((first, seen)) if *seen != iface => {
+                    return Err(Error::Conflict {
+                        interface: iface.name().to_string(),
+                        paths: [first.to_path_buf(), source.path.clone()],
+                    });
+                }
+                Some(_) => {}
+                None => definitions.push((&source.path, iface)),
+            }
+            registrations.push((path, iface));
+        }
+    }
+    Ok(registrations)
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any characters and `?`
+/// any one character.
+pub(crate) fn matches_glob(pattern: &str, name: &str) -> bool {
+    let mut chars = pattern.chars();
+    match chars.next() {
+        None => name.is_empty(),
+        Some('*') => name
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(Some(name.len()))
+            .any(|i| matches_glob(chars.as_str(), &name[i..])),
+        Some('?') => {
+            let mut name = name.chars();
+            name.next().is_some() && matches_glob(chars.as_str(), name.as_str())
+        }
+        Some(c) => name
+            .strip_prefix(c)
+            .is_some_and(|name| matches_glob(chars.as_str(), name)),
+    }
+}
+
+pub(crate) fn is_glob(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?'])
+}
+
+/// The files matching a glob pattern, sorted.
+fn glob(pattern: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut matches = vec![PathBuf::new()];
+    let components: Vec<_> = pattern.components().collect();
+    for (idx, component) in components.iter().enumerate() {
+        let last = idx + 1 == components.len();
+        let part = match component {
+            Component::Normal(part) => part.to_string_lossy(),
+            _ => {
+                for path in &mut matches {
+                    path.push(component);
+                }
+                continue;
+            }
+        };
+        if !is_glob(Path::new(part.as_ref())) {
+            matches = matches
+                .into_iter()
+                .map(|path| path.join(part.as_ref()))
+                .filter(|path| if last { path.is_file() } else { path.is_dir() })
+                .collect();
+            continue;
+        }
+
+        let mut next = Vec::new();
+        for dir in &matches {
+            let listed = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir.as_path()
+            };
+            let io_err = |source| Error::Io {
+                path: listed.to_path_buf(),
+                source,
+            };
+            for entry in fs::read_dir(listed).map_err(io_err)? {
+                let entry = entry.map_err(io_err)?;
+                let name = entry.file_name();
+                let path = dir.join(&name);
+                let wanted = if last { path.is_file() } else { path.is_dir() };
+                if wanted && matches_glob(&part, &name.to_string_lossy()) {
+                    next.push(path);
+                }
+            }
+        }
+        matches = next;
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// `path` itself, or the `*.xml` files in it (sorted) if it is a directory.
+fn xml_files(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let io_err = |source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut files = Vec::new();
+    for entry in fs::read_dir(path).map_err(io_err)? {
+        let file = entry.map_err(io_err)?.path();
+        if file.extension().is_some_and(|ext| ext == "xml") {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_patterns() {
+        assert!(matches_glob("org.example.Foo", "org.example.Foo"));
+        assert!(!matches_glob("org.example.Foo", "org.example.Foo1"));
+        assert!(matches_glob("org.example.*", "org.example.Foo"));
+        assert!(matches_glob("org.*.Foo", "org.example.Foo"));
+        assert!(matches_glob("*", ""));
+        assert!(matches_glob("*.xml", "foo.xml"));
+        assert!(!matches_glob("*.xml", "foo.xml.bak"));
+        assert!(matches_glob("foo?.xml", "foo1.xml"));
+        assert!(!matches_glob("foo?.xml", "foo.xml"));
+    }
+
+    #[test]
+    fn conflicting_definitions() {
+        let source = |path: &str, xml: &str| Source::from_xml(path, xml.to_string()).unwrap();
+        let a = source(
+            "a.xml",
+            r#"<node><interface name="org.example.Foo"><method name="Bar"/></interface></node>"#,
+        );
+        let b = source(
+            "b.xml",
+            r#"<node name="/b"><interface name="org.example.Foo"><method name="Bar"/></interface></node>"#,
+        );
+        let c = source(
+            "c.xml",
+            r#"<node><interface name="org.example.Foo"><method name="Baz"/></interface></node>"#,
+        );
+
+        assert_eq!(merged_registrations(&[a.clone(), b]).unwrap().len(), 2);
+        match merged_registrations(&[a, c]) {
+            Err(Error::Conflict { interface, paths }) => {
+                assert_eq!(interface, "org.example.Foo");
+                assert_eq!(paths, [PathBuf::from("a.xml"), PathBuf::from("c.xml")]);
+            }
+            other => panic!("expected a conflict, got {:?}", other.map(|r| r.len())),
+        }
+    }
+}
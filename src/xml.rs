@@ -0,0 +1,260 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// An XML element with its attributes in document order, for checks that
+/// need more than the validated `zbus_xml` model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Element {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    /// Child elements and text, in document order.
+    pub(crate) content: Vec<Content>,
+    /// Comments right before the start tag.
+    pub(crate) comments: Vec<String>,
+    /// Comments after the last child, before the end tag.
+    pub(crate) end_comments: Vec<String>,
+    /// 1-based line of the start tag.
+    pub(crate) line: usize,
+}
+
+/// A child of an element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Content {
+    Element(Element),
+    /// Unescaped character data, CDATA sections included, with whitespace
+    /// between elements.
+    Text(String),
+}
+
+/// A parsed document: the root element and the markup around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Document {
+    /// The XML declaration, doctype, processing instructions and comments
+    /// before the root element, verbatim.
+    pub(crate) prolog: Vec<String>,
+    pub(crate) root: Element,
+    /// Comments and processing instructions after the root element.
+    pub(crate) epilog: Vec<String>,
+}
+
+/// A syntax error, at a 1-based line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SyntaxError {
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+impl Element {
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The child elements.
+    pub(crate) fn children(&self) -> impl Iterator<Item = &Element> {
+        self.content.iter().filter_map(|content| match content {
+            Content::Element(element) => Some(element),
+            Content::Text(_) => None,
+        })
+    }
+
+    /// The child elements named `name`.
+    pub(crate) fn children_named<'e>(
+        &'e self,
+        name: &'e str,
+    ) -> impl Iterator<Item = &'e Element> + 'e {
+        self.children().filter(move |child| child.name == name)
+    }
+
+    /// Whether the element holds text other than whitespace.
+    pub(crate) fn has_text(&self) -> bool {
+        self.content.iter().any(|content| match content {
+            Content::Text(text) => !text.trim().is_empty(),
+            Content::Element(_) => false,
+        })
+    }
+}
+
+/// Parses the root element of `xml`.
+pub(crate) fn parse(xml: &str) -> Result<Element, SyntaxError> {
+    parse_document(xml).map(|document| document.root)
+}
+
+/// Parses `xml`, keeping comments with the element they precede.
+pub(crate) fn parse_document(xml: &str) -> Result<Document, SyntaxError> {
+    let mut reader = Reader::from_str(xml);
+    let line_at = |offset: u64| {
+        xml.as_bytes()[..offset as usize]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count()
+            + 1
+    };
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    let (mut prolog, mut epilog) = (Vec::new(), Vec::new());
+    let mut comments = Vec::new();
+
+    loop {
+        let start = reader.buffer_position();
+        let event = reader.read_event().map_err(|err| SyntaxError {
+            line: line_at(reader.error_position()),
+            message: err.to_string(),
+        })?;
+        let error = |message: String| SyntaxError {
+            line: line_at(start),
+            message,
+        };
+        match event {
+            Event::Start(tag) => {
+                let mut element = element(&tag, line_at(start)).map_err(error)?;
+                element.comments = std::mem::take(&mut comments);
+                stack.push(element);
+            }
+            Event::Empty(tag) => {
+                let mut element = element(&tag, line_at(start)).map_err(error)?;
+                element.comments = std::mem::take(&mut comments);
+                match stack.last_mut() {
+                    Some(parent) => parent.content.push(Content::Element(element)),
+                    None if root.is_none() => root = Some(element),
+                    None => return Err(error("more than one root element".to_string())),
+                }
+            }
+            Event::End(_) => {
+                let mut element = stack.pop().expect("end tags are matched by the reader");
+                element.end_comments = std::mem::take(&mut comments);
+                match stack.last_mut() {
+                    Some(parent) => parent.content.push(Content::Element(element)),
+                    None if root.is_none() => root = Some(element),
+                    None => return Err(error("more than one root element".to_string())),
+                }
+            }
+            Event::Text(text) if !stack.is_empty() => {
+                let text = text.unescape().map_err(|err| error(err.to_string()))?;
+                push_text(stack.last_mut().unwrap(), &text);
+            }
+            Event::CData(text) if !stack.is_empty() => {
+                push_text(stack.last_mut().unwrap(), &raw(&text))
+            }
+            Event::Comment(text) if !stack.is_empty() => comments.push(raw(&text)),
+            Event::Comment(text) => {
+                outside(&mut prolog, &mut epilog, &root).push(format!("<!--{}-->", raw(&text)))
+            }
+            Event::PI(text) if stack.is_empty() => {
+                outside(&mut prolog, &mut epilog, &root).push(format!("<?{}?>", raw(&text)))
+            }
+            Event::Decl(decl) => prolog.push(format!("<?{}?>", raw(&decl))),
+            Event::DocType(text) => prolog.push(format!("<!DOCTYPE {}>", raw(&text))),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(SyntaxError {
+            line: open.line,
+            message: format!("<{}> is never closed", open.name),
+        });
+    }
+    let root = root.ok_or_else(|| SyntaxError {
+        line: 1,
+        message: "no root element".to_string(),
+    })?;
+    Ok(Document {
+        prolog,
+        root,
+        epilog,
+    })
+}
+
+/// Where markup outside the root element goes: before or after it.
+fn outside<'d>(
+    prolog: &'d mut Vec<String>,
+    epilog: &'d mut Vec<String>,
+    root: &Option<Element>,
+) -> &'d mut Vec<String> {
+    match root {
+        Some(_) => epilog,
+        None => prolog,
+    }
+}
+
+/// Appends `text` to the content of `element`, joining it to text right
+/// before, as a CDATA section next to other text is.
+fn push_text(element: &mut Element, text: &str) {
+    match element.content.last_mut() {
+        Some(Content::Text(last)) => last.push_str(text),
+        _ => element.content.push(Content::Text(text.to_string())),
+    }
+}
+
+fn raw(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn element(tag: &BytesStart, line: usize) -> Result<Element, String> {
+    let mut attributes = Vec::new();
+    for attribute in tag.attributes() {
+        let attribute = attribute.map_err(|err| err.to_string())?;
+        let value = attribute.unescape_value().map_err(|err| err.to_string())?;
+        attributes.push((
+            String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+            value.into_owned(),
+        ));
+    }
+    Ok(Element {
+        name: String::from_utf8_lossy(tag.name().as_ref()).into_owned(),
+        attributes,
+        content: Vec::new(),
+        comments: Vec::new(),
+        end_comments: Vec::new(),
+        line,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tree_with_lines() {
+        let xml = "<!DOCTYPE node>\n<node>\n  <interface name=\"a.B\">\n    <method name=\"C\"/>\n  </interface>\n</node>\n";
+        let root = parse(xml).unwrap();
+        assert_eq!(root.name, "node");
+        assert_eq!(root.line, 2);
+        let iface = root.children().next().unwrap();
+        assert_eq!(iface.attribute("name"), Some("a.B"));
+        assert_eq!(iface.line, 3);
+        assert_eq!(iface.children_named("method").next().unwrap().line, 4);
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        assert_eq!(parse("<node>\n<interface>\n</node>").unwrap_err().line, 3);
+        assert_eq!(parse("<node>\n<interface>").unwrap_err().line, 2);
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let xml = "<?xml version=\"1.0\"?>\n<!-- a -->\n<node>\n  <!-- b -->\n  <interface name=\"a.B\">\n    <!-- c -->\n  </interface>\n</node>\n<!-- d -->\n";
+        let document = parse_document(xml).unwrap();
+        assert_eq!(document.prolog, ["<?xml version=\"1.0\"?>", "<!-- a -->"]);
+        assert_eq!(document.epilog, ["<!-- d -->"]);
+        let iface = document.root.children().next().unwrap();
+        assert_eq!(iface.comments, [" b "]);
+        assert_eq!(iface.end_comments, [" c "]);
+    }
+
+    #[test]
+    fn keeps_text() {
+        let xml = "<node><doc:para>a &amp; <doc:ref>b</doc:ref><![CDATA[ <c>]]></doc:para></node>";
+        let para = parse(xml).unwrap().children().next().unwrap().clone();
+        assert!(para.has_text());
+        assert_eq!(para.content[0], Content::Text("a & ".to_string()));
+        assert_eq!(para.content[2], Content::Text(" <c>".to_string()));
+        assert!(!parse(xml).unwrap().has_text());
+    }
+}
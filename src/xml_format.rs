@@ -0,0 +1,169 @@
+use quick_xml::escape::{escape, partial_escape};
+use zbus_xml::Node;
+
+use crate::xml::{parse_document, Content, Element};
+
+/// How [`format_xml`] lays out introspection XML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlFormat {
+    /// Spaces per nesting level.
+    pub indent: usize,
+    /// Order interfaces and child nodes, and the annotations, methods, signals
+    /// and properties of interfaces, by name. Arguments keep their order.
+    pub sort: bool,
+}
+
+impl Default for XmlFormat {
+    fn default() -> Self {
+        XmlFormat {
+            indent: 2,
+            sort: false,
+        }
+    }
+}
+
+/// Attributes in the order they are written, before any others.
+const ATTRIBUTE_ORDER: &[&str] = &["name", "type", "direction", "access", "value"];
+
+/// Re-serializes introspection XML canonically: one element per line,
+/// indented by nesting, attributes in a fixed order and `direction="in"`
+/// spelled out on method arguments. Signal arguments lose their redundant
+/// `direction="out"`. Comments are kept with the element they precede.
+/// Elements holding text, such as `<doc:para>`, are written on one line with
+/// their content as it was; whitespace between other elements is dropped.
+///
+/// Fails if `zbus_xml` does not accept the XML.
+pub fn format_xml(xml: &str, format: &XmlFormat) -> Result<String, zbus_xml::Error> {
+    Node::from_reader(xml.as_bytes())?;
+    let document = parse_document(xml).expect("XML accepted by zbus_xml is well-formed");
+
+    let mut out = String::new();
+    for markup in &document.prolog {
+        out.push_str(markup);
+        out.push('\n');
+    }
+    write_element(&mut out, &document.root, None, 0, format);
+    for markup in &document.epilog {
+        out.push_str(markup);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn write_element(
+    out: &mut String,
+    element: &Element,
+    parent: Option<&str>,
+    depth: usize,
+    format: &XmlFormat,
+) {
+    let indent = " ".repeat(depth * format.indent);
+    for comment in &element.comments {
+        out.push_str(&format!("{}<!--{}-->\n", indent, comment));
+    }
+
+    out.push_str(&indent);
+    if element.has_text() {
+        write_inline(out, element, parent);
+        out.push('\n');
+        return;
+    }
+    write_start_tag(out, element, parent);
+    if element.children().next().is_none() && element.end_comments.is_empty() {
+        out.push_str("/>\n");
+        return;
+    }
+    out.push_str(">\n");
+
+    let mut children: Vec<&Element> = element.children().collect();
+    if format.sort && SORTED.contains(&element.name.as_str()) {
+        children.sort_by_key(|child| (rank(&element.name, &child.name), child.attribute("name")));
+    }
+    for child in children {
+        write_element(out, child, Some(&element.name), depth + 1, format);
+    }
+    let child_indent = " ".repeat((depth + 1) * format.indent);
+    for comment in &element.end_comments {
+        out.push_str(&format!("{}<!--{}-->\n", child_indent, comment));
+    }
+    out.push_str(&format!("{}</{}>\n", indent, element.name));
+}
+
+/// Writes `element` and its content without line breaks or indentation of
+/// its own, keeping text and the whitespace in it.
+fn write_inline(out: &mut String, element: &Element, parent: Option<&str>) {
+    write_start_tag(out, element, parent);
+    if element.content.is_empty() && element.end_comments.is_empty() {
+        out.push_str("/>");
+        return;
+    }
+    out.push('>');
+    for content in &element.content {
+        match content {
+            Content::Text(text) => out.push_str(&partial_escape(text.as_str())),
+            Content::Element(child) => {
+                for comment in &child.comments {
+                    out.push_str(&format!("<!--{}-->", comment));
+                }
+                write_inline(out, child, Some(&element.name));
+            }
+        }
+    }
+    for comment in &element.end_comments {
+        out.push_str(&format!("<!--{}-->", comment));
+    }
+    out.push_str(&format!("</{}>", element.name));
+}
+
+/// Writes `<name` and the attributes of `element`, leaving the tag open.
+fn write_start_tag(out: &mut String, element: &Element, parent: Option<&str>) {
+    out.push('<');
+    out.push_str(&element.name);
+    for (key, value) in attributes(element, parent) {
+        out.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+    }
+}
+
+/// The attributes of `element` in canonical order, with argument directions
+/// normalized.
+fn attributes<'e>(element: &'e Element, parent: Option<&str>) -> Vec<(&'e str, &'e str)> {
+    let mut attributes: Vec<(&str, &str)> = element
+        .attributes
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    if element.name == "arg" {
+        match parent {
+            Some("method") if element.attribute("direction").is_none() => {
+                attributes.push(("direction", "in"))
+            }
+            Some("signal") => attributes.retain(|&attr| attr != ("direction", "out")),
+            _ => {}
+        }
+    }
+    attributes.sort_by_key(|(key, _)| {
+        ATTRIBUTE_ORDER
+            .iter()
+            .position(|known| known == key)
+            .unwrap_or(ATTRIBUTE_ORDER.len())
+    });
+    attributes
+}
+
+/// Elements whose children are sorted. Arguments are not, their order being
+/// part of the signature.
+const SORTED: &[&str] = &["node", "interface"];
+
+/// Where a `child` element of a sorted `parent` goes, before its name is
+/// compared.
+fn rank(parent: &str, child: &str) -> usize {
+    let order: &[&str] = if parent == "node" {
+        &["interface", "node"]
+    } else {
+        &["annotation", "method", "signal", "property"]
+    };
+    order
+        .iter()
+        .position(|&name| name == child)
+        .unwrap_or(order.len())
+}
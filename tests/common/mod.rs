@@ -1,3 +1,6 @@
+use std::fs;
+use std::path::PathBuf;
+
 #[allow(dead_code)]
 pub fn assert_contains(actual: &str, expected: &str) {
     if !actual.contains(expected) {
@@ -17,3 +20,30 @@ pub fn assert_not_contains(actual: &str, not_expected: &str) {
         );
     }
 }
+
+/// `code` without whitespace, and without the trailing commas that
+/// pretty-printing adds to lists it breaks over several lines, for matching
+/// snippets regardless of line breaks.
+#[allow(dead_code)]
+pub fn squeeze(code: &str) -> String {
+    code.split_whitespace()
+        .collect::<String>()
+        .replace(",)", ")")
+        .replace(",}", "}")
+        .replace(",>", ">")
+}
+
+/// A fresh directory `name` under `CARGO_TARGET_TMPDIR` holding `files`,
+/// given as relative paths and contents.
+#[allow(dead_code)]
+pub fn temp_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (path, contents) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
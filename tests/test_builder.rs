@@ -0,0 +1,187 @@
+use std::fs;
+use std::path::PathBuf;
+
+use zbus_xml_gen::{Builder, Error, Options};
+
+mod common;
+use common::{assert_contains, assert_not_contains, temp_dir};
+
+const FOO_XML: &str = r#"
+<node>
+  <interface name="org.example.Foo">
+    <method name="Bar"><arg name="x" type="i" direction="in"/></method>
+  </interface>
+</node>
+"#;
+
+const BAZ_XML: &str = r#"
+<node>
+  <interface name="org.example.Baz">
+    <property name="Level" type="u" access="read"/>
+  </interface>
+</node>
+"#;
+
+/// A fresh directory holding `xml/foo.xml`, `xml/baz.xml`, `xml/notes.txt`
+/// and an empty `out` directory.
+fn setup(name: &str) -> PathBuf {
+    let dir = temp_dir(
+        name,
+        &[
+            ("xml/foo.xml", FOO_XML),
+            ("xml/baz.xml", BAZ_XML),
+            ("xml/notes.txt", "not xml"),
+        ],
+    );
+    fs::create_dir_all(dir.join("out")).unwrap();
+    dir
+}
+
+#[test]
+fn builder_writes_one_file_per_input() {
+    let dir = setup("one_file_per_input");
+    let written = Builder::new()
+        .input(dir.join("xml"))
+        .server(true)
+        .out_dir(dir.join("out"))
+        .generate()
+        .unwrap();
+
+    assert_eq!(written, [dir.join("out/baz.rs"), dir.join("out/foo.rs")]);
+    let foo = fs::read_to_string(dir.join("out/foo.rs")).unwrap();
+    assert_contains(&foo, "pub trait FooDelegate");
+    assert_not_contains(&foo, "BazDelegate");
+}
+
+#[test]
+fn builder_combined_output() {
+    let dir = setup("combined_output");
+    let written = Builder::new()
+        .input(dir.join("xml/foo.xml"))
+        .input(dir.join("xml/baz.xml"))
+        .combined("bindings.rs")
+        .out_dir(dir.join("out"))
+        .generate()
+        .unwrap();
+
+    assert_eq!(written, [dir.join("out/bindings.rs")]);
+    let code = fs::read_to_string(&written[0]).unwrap();
+    assert_contains(&code, "pub trait Foo");
+    assert_contains(&code, "pub trait Baz");
+}
+
+#[test]
+fn builder_glob_input() {
+    let dir = setup("glob_input");
+    let written = Builder::new()
+        .input(dir.join("xml/f*.xml"))
+        .out_dir(dir.join("out"))
+        .generate()
+        .unwrap();
+    assert_eq!(written, [dir.join("out/foo.rs")]);
+}
+
+#[test]
+fn builder_reports_conflicts() {
+    let dir = setup("reports_conflicts");
+    fs::write(dir.join("xml/baz.xml"), FOO_XML.replace("Bar", "Baz")).unwrap();
+    let err = Builder::new()
+        .input(dir.join("xml"))
+        .combined("bindings.rs")
+        .out_dir(dir.join("out"))
+        .generate()
+        .unwrap_err();
+    match err {
+        Error::Conflict { interface, paths } => {
+            assert_eq!(interface, "org.example.Foo");
+            assert_eq!(paths, [dir.join("xml/baz.xml"), dir.join("xml/foo.xml")]);
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn builder_reports_output_collisions() {
+    let dir = setup("reports_output_collisions");
+    fs::create_dir_all(dir.join("other")).unwrap();
+    fs::write(dir.join("other/foo.xml"), BAZ_XML).unwrap();
+    let err = Builder::new()
+        .input(dir.join("xml/foo.xml"))
+        .input(dir.join("other/foo.xml"))
+        .out_dir(dir.join("out"))
+        .generate()
+        .unwrap_err();
+    match err {
+        Error::OutputCollision { file, inputs } => {
+            assert_eq!(file, "foo.rs");
+            assert_eq!(inputs, [dir.join("xml/foo.xml"), dir.join("other/foo.xml")]);
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(!dir.join("out/foo.rs").exists());
+}
+
+#[test]
+fn builder_skips_unchanged_files() {
+    let dir = setup("skips_unchanged_files");
+    let builder = Builder::new()
+        .input(dir.join("xml/foo.xml"))
+        .out_dir(dir.join("out"));
+    let out = builder.generate().unwrap().remove(0);
+    let modified = fs::metadata(&out).unwrap().modified().unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    builder.generate().unwrap();
+    assert_eq!(fs::metadata(&out).unwrap().modified().unwrap(), modified);
+}
+
+#[test]
+fn builder_reports_invalid_xml() {
+    let dir = setup("invalid_xml");
+    fs::write(dir.join("xml/foo.xml"), "<node><interface>").unwrap();
+    let err = Builder::new()
+        .input(dir.join("xml/foo.xml"))
+        .out_dir(dir.join("out"))
+        .generate()
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Xml { .. }));
+    assert_contains(&err.to_string(), "foo.xml");
+}
+
+#[test]
+fn builder_reports_missing_input() {
+    let dir = setup("missing_input");
+    let err = Builder::new()
+        .input(dir.join("xml/missing.xml"))
+        .out_dir(dir.join("out"))
+        .generate()
+        .unwrap_err();
+
+    assert!(matches!(err, Error::Io { .. }));
+}
+
+#[test]
+fn builder_writes_provenance_header() {
+    let dir = setup("provenance_header");
+    let written = Builder::new()
+        .input(dir.join("xml/foo.xml"))
+        .server(true)
+        .options(Options {
+            allow: vec!["dead_code".to_string()],
+            ..Options::default()
+        })
+        .out_dir(dir.join("out"))
+        .generate()
+        .unwrap();
+
+    let code = fs::read_to_string(&written[0]).unwrap();
+    assert!(code.starts_with("// @generated by zbus-xml-gen "));
+    assert_contains(
+        &code,
+        &format!("// Sources: {}\n", dir.join("xml/foo.xml").display()),
+    );
+    assert_contains(&code, "// Options: server\n");
+    // Inner attributes would break `include!`.
+    assert_not_contains(&code, "#![allow");
+}
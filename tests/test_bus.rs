@@ -0,0 +1,106 @@
+#![cfg(feature = "bus")]
+
+use std::os::unix::net::UnixStream;
+use std::thread;
+
+use zbus::blocking::{connection, Connection};
+use zbus::interface;
+use zbus_xml_gen::{generate_from_sources, introspect, Error, Mode, Options};
+
+mod common;
+use common::{assert_contains, assert_not_contains, squeeze};
+
+struct Foo;
+
+#[interface(name = "org.example.Foo")]
+impl Foo {
+    fn bar(&self, x: i32) -> String {
+        x.to_string()
+    }
+
+    #[zbus(property)]
+    fn level(&self) -> u32 {
+        1
+    }
+}
+
+struct Child;
+
+#[interface(name = "org.example.Child")]
+impl Child {
+    fn ping(&self) {}
+}
+
+/// A peer-to-peer client connection to a server with `Foo` at `/org/example`
+/// and `Child` at `/org/example/child` and `/org/example/child/nested`.
+fn connect() -> (Connection, Connection) {
+    let (client, server) = UnixStream::pair().unwrap();
+    let server = thread::spawn(move || {
+        connection::Builder::unix_stream(server)
+            .server(zbus::Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at("/org/example", Foo)
+            .unwrap()
+            .serve_at("/org/example/child", Child)
+            .unwrap()
+            .serve_at("/org/example/child/nested", Child)
+            .unwrap()
+            .build()
+            .unwrap()
+    });
+    let client = connection::Builder::unix_stream(client)
+        .p2p()
+        .build()
+        .unwrap();
+    (client, server.join().unwrap())
+}
+
+#[test]
+fn introspects_object_tree() {
+    let (client, _server) = connect();
+    let source = introspect(&client, None, "/").unwrap();
+    assert_eq!(source.path().to_str(), Some("/"));
+
+    let options = Options {
+        serve: true,
+        ..Options::default()
+    };
+    let code = generate_from_sources(&[source], Mode::Server, &options).unwrap();
+    assert_contains(&code, "pub trait FooDelegate");
+    assert_contains(
+        &code,
+        "async fn bar(&self, x: i32) -> zbus::fdo::Result<String>;",
+    );
+    assert_contains(&code, "pub trait ChildDelegate");
+    assert_contains(&code, "builder.serve_at(\"/org/example\", Foo::new(");
+    assert_contains(
+        &squeeze(&code),
+        &squeeze("builder.serve_at(\"/org/example/child\", Child::new("),
+    );
+    assert_contains(
+        &squeeze(&code),
+        &squeeze("builder.serve_at(\"/org/example/child/nested\", "),
+    );
+    assert_not_contains(&code, "Introspectable");
+    assert_not_contains(&code, "PeerDelegate");
+}
+
+#[test]
+fn introspects_subtree() {
+    let (client, _server) = connect();
+    let source = introspect(&client, None, "/org/example/child").unwrap();
+    let code = generate_from_sources(&[source], Mode::Client, &Options::default()).unwrap();
+    assert_contains(&code, "pub trait Child {");
+    assert_not_contains(&code, "pub trait Foo {");
+}
+
+#[test]
+fn reports_failed_calls() {
+    let (client, server) = connect();
+    drop(server);
+    match introspect(&client, None, "/") {
+        Err(Error::Bus { object, .. }) => assert_eq!(object, "/"),
+        other => panic!("expected a D-Bus error, got {:?}", other.map(|_| ())),
+    }
+}
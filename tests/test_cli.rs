@@ -0,0 +1,366 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod common;
+use common::{assert_contains, temp_dir};
+
+const XML: &str = r#"
+<node>
+  <interface name="org.example.Foo">
+    <method name="Bar"><arg name="x" type="i" direction="in"/></method>
+  </interface>
+</node>
+"#;
+
+/// A fresh directory holding `foo.xml`.
+fn setup(name: &str) -> PathBuf {
+    temp_dir(name, &[("foo.xml", XML)])
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_zbus-xml-gen"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn cli_out_dir() {
+    let dir = setup("cli_out_dir");
+    let output = run(&dir, &["--out-dir", "out", "foo.xml"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let root = fs::read_to_string(dir.join("out/mod.rs")).unwrap();
+    assert_contains(&root, "pub use self::org_example_foo::*;");
+    let module = fs::read_to_string(dir.join("out/org_example_foo.rs")).unwrap();
+    assert_contains(&module, "pub trait Foo {");
+}
+
+#[test]
+fn cli_check() {
+    let dir = setup("cli_check");
+    assert!(run(&dir, &["--out-dir", "out", "foo.xml"]).status.success());
+    assert!(run(&dir, &["--check", "--out-dir", "out", "foo.xml"])
+        .status
+        .success());
+
+    fs::write(dir.join("foo.xml"), XML.replace("Bar", "Baz")).unwrap();
+    let output = run(&dir, &["--check", "--out-dir", "out", "foo.xml"]);
+    assert_eq!(output.status.code(), Some(1));
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert_contains(&diff, "--- out/org_example_foo.rs");
+    assert_contains(&diff, "-    fn bar(&self, x: i32) -> zbus::Result<()>;");
+    assert_contains(&diff, "+    fn baz(&self, x: i32) -> zbus::Result<()>;");
+
+    // The tree is left untouched.
+    let module = fs::read_to_string(dir.join("out/org_example_foo.rs")).unwrap();
+    assert_contains(&module, "fn bar(");
+
+    // Modules of removed interfaces are reported too.
+    fs::write(dir.join("foo.xml"), XML).unwrap();
+    fs::write(dir.join("out/org_example_old.rs"), "pub trait Old {}\n").unwrap();
+    let output = run(&dir, &["--check", "--out-dir", "out", "foo.xml"]);
+    assert_eq!(output.status.code(), Some(1));
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert_contains(&diff, "--- out/org_example_old.rs\n+++ /dev/null\n");
+    assert_contains(&diff, "-pub trait Old {}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_contains(
+        &stderr,
+        "out/org_example_old.rs is no longer generated; remove it",
+    );
+}
+
+#[test]
+fn cli_errors_exit_with_status_2() {
+    let dir = setup("cli_errors_exit_with_status_2");
+    fs::write(dir.join("bad.xml"), "<node><interface>").unwrap();
+
+    let output = run(&dir, &["--check", "--out-dir", "out", "bad.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+    let output = run(&dir, &["--check", "--out-dir", "out", "missing.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+    let output = run(&dir, &["bad.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn cli_server_with_signals() {
+    let dir = setup("cli_server_with_signals");
+    fs::write(
+        dir.join("foo.xml"),
+        XML.replace("</method>", "</method><signal name=\"Changed\"/>"),
+    )
+    .unwrap();
+    let output = run(&dir, &["--server", "foo.xml"]);
+    assert!(output.status.success());
+    let code = String::from_utf8(output.stdout).unwrap();
+    assert_contains(
+        &code,
+        "    #[zbus(signal)]\n    async fn changed(emitter: SignalEmitter<'_>) -> Result<()>;\n",
+    );
+}
+
+#[test]
+fn cli_provenance_header() {
+    let dir = setup("cli_provenance_header");
+    let output = run(&dir, &["--server", "--allow", "dead_code", "foo.xml"]);
+    assert!(output.status.success());
+    let code = String::from_utf8(output.stdout).unwrap();
+    assert!(code.starts_with("// @generated by zbus-xml-gen "));
+    assert_contains(&code, "// Sources: foo.xml\n");
+    assert_contains(&code, "// Input hash: fnv1a64:");
+    assert_contains(
+        &code,
+        "// Options: server allow=dead_code\n\n#![allow(dead_code)]\n\n",
+    );
+}
+
+#[test]
+fn cli_config() {
+    let dir = setup("cli_config");
+    fs::write(
+        dir.join("zbus-xml-gen.toml"),
+        "[[output]]\ninputs = [\"foo.xml\"]\nout-dir = \"out\"\nmode = \"server\"\n",
+    )
+    .unwrap();
+
+    let output = run(&dir, &[]);
+    assert!(output.status.success());
+    let module = fs::read_to_string(dir.join("out/org_example_foo.rs")).unwrap();
+    assert_contains(&module, "pub trait FooDelegate");
+    assert!(run(&dir, &["--check"]).status.success());
+
+    fs::write(dir.join("foo.xml"), XML.replace("Bar", "Baz")).unwrap();
+    let output = run(&dir, &["--check", "--config", "zbus-xml-gen.toml"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_contains(
+        &String::from_utf8(output.stdout).unwrap(),
+        "--- out/org_example_foo.rs",
+    );
+}
+
+#[test]
+fn cli_multiple_inputs() {
+    let dir = setup("cli_multiple_inputs");
+    fs::create_dir_all(dir.join("more")).unwrap();
+    let other = |name: &str| {
+        format!(
+            r#"<node><interface name="{}"><method name="Ping"/></interface></node>"#,
+            name
+        )
+    };
+    fs::write(dir.join("more/a1.xml"), other("org.a.Manager")).unwrap();
+    fs::write(dir.join("more/b1.xml"), other("org.b.Manager")).unwrap();
+    fs::write(dir.join("more/c.xml"), other("org.c.Unused")).unwrap();
+
+    // The same file twice, a glob, and a duplicate but identical definition.
+    fs::write(dir.join("copy.xml"), XML).unwrap();
+    let output = run(&dir, &["foo.xml", "more/?1.xml", "copy.xml", "foo.xml"]);
+    assert!(output.status.success());
+    let code = String::from_utf8(output.stdout).unwrap();
+    assert_contains(
+        &code,
+        "// Sources: foo.xml, more/a1.xml, more/b1.xml, copy.xml\n",
+    );
+    assert_contains(&code, "pub trait Foo {");
+    assert_contains(&code, "pub trait AManager {");
+    assert_contains(&code, "pub trait BManager {");
+    assert!(!code.contains("Unused"));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_contains(
+        &stderr,
+        "interface 'org.a.Manager' would be named `Manager`",
+    );
+}
+
+#[test]
+fn cli_reports_conflicts() {
+    let dir = setup("cli_reports_conflicts");
+    fs::write(dir.join("other.xml"), XML.replace("Bar", "Baz")).unwrap();
+    let output = run(&dir, &["foo.xml", "other.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_contains(
+        &String::from_utf8(output.stderr).unwrap(),
+        "interface 'org.example.Foo' is defined differently in foo.xml and other.xml",
+    );
+
+    let output = run(&dir, &["missing/*.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_contains(
+        &String::from_utf8(output.stderr).unwrap(),
+        "missing/*.xml: pattern matches no files",
+    );
+}
+
+#[test]
+fn cli_lint() {
+    let dir = setup("cli_lint");
+    fs::write(
+        dir.join("bad.xml"),
+        "<node>\n<interface name=\"org.example.Bad\">\n<method name=\"get\"/>\n</interface>\n</node>\n",
+    )
+    .unwrap();
+
+    let output = run(&dir, &["lint", "foo.xml", "bad.xml"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "bad.xml:3: warning[member-case]: method 'org.example.Bad.get' is not PascalCase\n"
+    );
+
+    let output = run(&dir, &["lint", "--fail-on", "warning", "bad.xml"]);
+    assert_eq!(output.status.code(), Some(1));
+    let output = run(&dir, &["lint", "-D", "member-case", "bad.xml"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_contains(
+        &String::from_utf8(output.stdout).unwrap(),
+        "error[member-case]",
+    );
+
+    // Levels and inputs from the configuration file.
+    fs::write(
+        dir.join("zbus-xml-gen.toml"),
+        "[[output]]\ninputs = [\"bad.xml\"]\nfile = \"out.rs\"\n\n[lint]\ndeny = [\"all\"]\n",
+    )
+    .unwrap();
+    let output = run(&dir, &["lint"]);
+    assert_eq!(output.status.code(), Some(1));
+    let output = run(&dir, &["lint", "-A", "member-case"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run(&dir, &["lint", "-D", "no-such-rule"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_diff() {
+    let dir = setup("cli_diff");
+    fs::write(dir.join("new.xml"), XML.replace("type=\"i\"", "type=\"u\"")).unwrap();
+
+    let output = run(&dir, &["diff", "foo.xml", "new.xml"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "breaking: org.example.Foo.Bar: in-args changed from (i) to (u)\n"
+    );
+
+    let output = run(&dir, &["diff", "--format", "json", "foo.xml", "new.xml"]);
+    let json = String::from_utf8(output.stdout).unwrap();
+    assert_contains(&json, "\"breaking\": true");
+    assert_contains(&json, "\"kind\": \"method-in-args-changed\"");
+    assert_contains(&json, "\"member\": \"Bar\"");
+
+    let output = run(&dir, &["diff", "--fail-on", "never", "foo.xml", "new.xml"]);
+    assert_eq!(output.status.code(), Some(0));
+    let output = run(&dir, &["diff", "foo.xml", "foo.xml"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    let output = run(&dir, &["diff", "foo.xml", "missing.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_fmt() {
+    let dir = setup("cli_fmt");
+    let output = run(&dir, &["fmt", "--check", "foo.xml"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_contains(
+        &String::from_utf8(output.stdout).unwrap(),
+        "+    <method name=\"Bar\">",
+    );
+
+    assert!(run(&dir, &["fmt", "foo.xml"]).status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("foo.xml")).unwrap(),
+        "<node>\n  <interface name=\"org.example.Foo\">\n    <method name=\"Bar\">\n      \
+         <arg name=\"x\" type=\"i\" direction=\"in\"/>\n    </method>\n  </interface>\n</node>\n"
+    );
+    assert!(run(&dir, &["fmt", "--check", "foo.xml"]).status.success());
+
+    fs::write(dir.join("bad.xml"), "<node><interface></node>").unwrap();
+    let output = run(&dir, &["fmt", "bad.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_docs() {
+    let dir = setup("cli_docs");
+    let output = run(&dir, &["docs", "--service", "org.example", "foo.xml"]);
+    assert!(output.status.success());
+    let docs = String::from_utf8(output.stdout).unwrap();
+    assert_contains(&docs, "### `Bar(i x)`");
+    assert_contains(&docs, "busctl call org.example / org.example.Foo Bar i 0");
+
+    let output = run(&dir, &["docs", "--format", "html", "foo.xml"]);
+    assert_contains(
+        &String::from_utf8(output.stdout).unwrap(),
+        "<h3><code>Bar(i x)</code></h3>",
+    );
+
+    let output = run(&dir, &["docs", "missing.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+    fs::write(dir.join("bad.xml"), "<node><interface></node>").unwrap();
+    let output = run(&dir, &["docs", "bad.xml"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_contains(&String::from_utf8(output.stderr).unwrap(), "bad.xml");
+}
+
+#[test]
+fn cli_emit_cli_client() {
+    let dir = setup("cli_emit_cli_client");
+    let output = run(&dir, &["--emit", "cli-client", "foo.xml"]);
+    assert!(output.status.success());
+    let code = String::from_utf8(output.stdout).unwrap();
+    assert_contains(&code, "// @generated");
+    assert_contains(&code, "enum FooCommand {");
+    assert_contains(&code, "fn main() {");
+
+    let output = run(&dir, &["--emit", "cli-client", "--server", "foo.xml"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn cli_emit_ir_json() {
+    let dir = setup("cli_emit_ir_json");
+    let output = run(&dir, &["--emit", "ir-json", "foo.xml"]);
+    assert!(output.status.success());
+    let json = String::from_utf8(output.stdout).unwrap();
+    assert_contains(&json, "\"schema_version\": 1");
+    assert_contains(&json, "\"proxy_name\": \"FooProxy\"");
+}
+
+#[test]
+fn cli_from_rust() {
+    let dir = setup("cli_from_rust");
+    fs::write(
+        dir.join("proxy.rs"),
+        "#[zbus::proxy(interface = \"org.example.Foo\")]\ntrait Foo {\n    \
+         fn bar(&self, x: Point) -> zbus::Result<()>;\n}\n",
+    )
+    .unwrap();
+
+    let output = run(&dir, &["from-rust", "--type", "Point=(ii)", "proxy.rs"]);
+    assert!(output.status.success());
+    assert_contains(
+        &String::from_utf8(output.stdout).unwrap(),
+        "<arg name=\"x\" type=\"(ii)\" direction=\"in\"/>",
+    );
+
+    let output = run(&dir, &["from-rust", "proxy.rs"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_contains(
+        &String::from_utf8(output.stderr).unwrap(),
+        "proxy.rs: cannot map `Point` to a D-Bus type",
+    );
+
+    let output = run(&dir, &["from-rust", "missing.rs"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_contains(&String::from_utf8(output.stderr).unwrap(), "missing.rs: ");
+}
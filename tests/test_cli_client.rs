@@ -0,0 +1,92 @@
+use zbus_xml_gen::{generate_cli_client_from_xml, Options};
+
+mod common;
+use common::{assert_contains, assert_not_contains, squeeze};
+
+const XML: &str = r#"
+<node>
+  <interface name="org.example.Thermostat">
+    <method name="SetTarget">
+      <arg name="celsius" type="d" direction="in"/>
+    </method>
+    <method name="Configure">
+      <arg name="name" type="s" direction="in"/>
+      <arg name="zones" type="a(iu)" direction="in"/>
+      <arg name="ok" type="b" direction="out"/>
+    </method>
+    <method name="Open">
+      <arg name="fd" type="h" direction="in"/>
+    </method>
+    <property name="Level" type="u" access="readwrite"/>
+    <property name="Mode" type="s" access="read"/>
+  </interface>
+</node>
+"#;
+
+#[test]
+fn cli_client_commands() {
+    let code = generate_cli_client_from_xml(XML, &Options::default());
+    assert_contains(&code, "#[proxy(");
+    assert_contains(&code, "enum ThermostatCommand {");
+    assert_contains(&code, "    SetTarget {\n");
+    assert_contains(&code, "    GetLevel,\n");
+    assert_contains(&code, "    SetLevel {\n");
+    assert_contains(&code, "    GetMode,\n");
+    assert_not_contains(&code, "SetMode");
+    assert_not_contains(&code, "Open");
+    assert_contains(&code, "command: ThermostatCommand,");
+    assert_contains(&code, "ThermostatProxyBlocking::builder(connection)");
+    assert_contains(&code, "fn main() {");
+}
+
+#[test]
+fn cli_client_converts_arguments() {
+    let code = generate_cli_client_from_xml(XML, &Options::default());
+    assert_contains(&code, "let celsius = json_f64(&celsius)?;");
+    assert_not_contains(&code, "let name = ");
+    assert_contains(
+        &squeeze(&code),
+        &squeeze("let zones = json_array(&zones)?.iter().map(|item0| -> ArgResult<_> { Ok({ let fields1 = \
+         json_struct(item0, 2)?; (json_int::<i32>(&fields1[0])?, json_int::<u32>(&fields1[1])?) }) \
+         }).collect::<ArgResult<Vec<_>>>()?;"),
+    );
+    assert_contains(&code, "let reply = proxy.configure(name, zones)?;");
+    assert_contains(&code, "proxy.set_level(value)?;");
+    assert_contains(&code, "fn json_int<");
+    assert_not_contains(&code, "fn json_variant(");
+}
+
+#[test]
+fn cli_client_options() {
+    let options = Options {
+        default_service: Some("org.example.Thermostat".to_string()),
+        default_path: Some("/org/example/Thermostat".to_string()),
+        ..Options::default()
+    };
+    let code = generate_cli_client_from_xml(XML, &options);
+    assert_contains(
+        &code,
+        "#[arg(long, default_value = \"org.example.Thermostat\")]",
+    );
+    assert_contains(
+        &code,
+        "#[arg(long, default_value = \"/org/example/Thermostat\")]",
+    );
+}
+
+#[test]
+fn cli_client_several_interfaces() {
+    let xml = r#"
+<node>
+  <interface name="org.example.Foo"><method name="Ping"/></interface>
+  <interface name="org.example.Bar"><method name="Ping"/></interface>
+</node>
+"#;
+    let code = generate_cli_client_from_xml(xml, &Options::default());
+    assert_contains(&code, "enum Command {");
+    assert_contains(&code, "command: FooCommand,");
+    assert_contains(
+        &code,
+        "Command::Bar { command } => {\n                    run_bar(",
+    );
+}
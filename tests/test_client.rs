@@ -1,5 +1,8 @@
 use zbus_xml_gen::generate_client_proxies_from_xml;
 
+mod common;
+use common::squeeze;
+
 const XML: &str = r#"
 <node>
   <interface name="org.example.Complex">
@@ -94,7 +97,7 @@ macro_rules! tests {
       #[test]
       fn $name() {
         let actual = generate_client_proxies_from_xml(XML);
-        if !actual.contains($expected) {
+        if !squeeze(&actual).contains(&squeeze($expected)) {
           println!("\n=== GENERATED OUTPUT ===\n{}\n=========================", actual);
           panic!("Assertion failed: expected snippet not found:\n{}", $expected);
         }
@@ -107,7 +110,7 @@ macro_rules! tests {
 tests!([
     (
         client_trait_complex_decl,
-        r#"#[proxy(interface = "org.example.Complex" , assume_defaults = true)]
+        r#"#[proxy(interface = "org.example.Complex", assume_defaults = true)]
 pub trait Complex {"#
     ),
     (client_trait_second_decl, "pub trait Second {")
@@ -177,3 +180,96 @@ tests!([
   (client_signal_items_updated, "fn items_updated(&self) -> zbus::Result<zbus::SignalStream<Vec<(String, String, String, std::collections::HashMap<String, String>, u16)>>>;"),
   (client_simple_signal, "fn simple_signal(&self) -> zbus::Result<zbus::SignalStream<()>>;")
 ]);
+
+#[test]
+fn client_renames_and_defaults() {
+    use zbus_xml_gen::{generate_from_xml, Mode, Options};
+
+    let mut options = Options {
+        default_service: Some("org.example.Service".to_string()),
+        default_path: Some("/org/example/Complex".to_string()),
+        ..Options::default()
+    };
+    options.renames.insert(
+        "org.example.Complex".to_string(),
+        "ComplexThing".to_string(),
+    );
+    let actual = generate_from_xml(XML, Mode::Client, &options);
+    assert!(squeeze(&actual).contains(&squeeze(
+        r#"#[proxy(interface = "org.example.Complex", default_service = "org.example.Service", default_path = "/org/example/Complex")]
+pub trait ComplexThing {"#
+    )));
+    assert!(actual.contains("pub trait Second {"));
+}
+
+#[test]
+fn client_name_constants() {
+    use zbus_xml_gen::{generate_from_xml, Mode, Options};
+
+    let xml = r#"
+<node>
+  <interface name="org.example.Complex">
+    <method name="Get"/>
+    <method name="Type"/>
+    <property name="Get" type="u" access="read"/>
+    <property name="Interface" type="s" access="read"/>
+    <signal name="StateChanged"/>
+  </interface>
+</node>
+"#;
+    let options = Options {
+        names: true,
+        ..Options::default()
+    };
+    let actual = generate_from_xml(xml, Mode::Client, &options);
+    assert!(actual.contains(
+        "pub mod names {\n    use zbus::names::{InterfaceName, MemberName};\n\n    \
+         /// Names of `org.example.Complex`.\n    pub mod complex {"
+    ));
+    assert!(squeeze(&actual).contains(&squeeze(
+        "pub const INTERFACE: InterfaceName<'static> = \
+         InterfaceName::from_static_str_unchecked(\"org.example.Complex\");"
+    )));
+    assert!(squeeze(&actual).contains(&squeeze(
+        "pub const STATE_CHANGED: MemberName<'static> = \
+         MemberName::from_static_str_unchecked(\"StateChanged\");"
+    )));
+    assert!(actual.contains("pub const TYPE: MemberName<'static>"));
+    assert!(actual.contains("pub const INTERFACE_2: MemberName<'static>"));
+    assert_eq!(
+        actual.matches("from_static_str_unchecked(\"Get\")").count(),
+        1
+    );
+
+    let actual = generate_from_xml(xml, Mode::Client, &Options::default());
+    assert!(!actual.contains("pub mod names"));
+}
+
+#[test]
+fn client_sanitizes_member_names_like_server() {
+    use zbus_xml_gen::{generate_from_xml, Mode, Options};
+
+    let xml = r#"
+<node>
+  <interface name="org.example.Keywords">
+    <method name="Type"/>
+    <property name="Async" type="u" access="read"/>
+    <signal name="Yield"/>
+  </interface>
+</node>
+"#;
+    let client = generate_from_xml(xml, Mode::Client, &Options::default());
+    let server = generate_from_xml(xml, Mode::Server, &Options::default());
+    for name in ["type_", "async_", "yield_"].iter() {
+        assert!(
+            client.contains(&format!("fn {}(&self", name)),
+            "client lacks {}",
+            name
+        );
+        assert!(
+            server.contains(&format!("fn {}(", name)),
+            "server lacks {}",
+            name
+        );
+    }
+}
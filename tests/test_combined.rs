@@ -0,0 +1,131 @@
+use zbus_xml_gen::{generate_from_xml, Mode, Options};
+
+mod common;
+use common::{assert_contains, assert_not_contains};
+
+const XML: &str = r#"
+<node>
+  <interface name="org.example.Foo">
+    <method name="GetItems">
+      <arg name="items" type="a(su)" direction="out"/>
+    </method>
+    <method name="SetItems">
+      <arg name="items" type="a(su)" direction="in"/>
+    </method>
+    <signal name="Changed">
+      <arg name="hints" type="a{sv}"/>
+    </signal>
+    <property name="Count" type="u" access="read"/>
+  </interface>
+</node>
+"#;
+
+fn combined() -> String {
+    generate_from_xml(XML, Mode::Both, &Options::default())
+}
+
+#[test]
+fn combined_modules() {
+    let code = combined();
+    assert_contains(&code, "pub mod types {");
+    assert_contains(&code, "pub mod client {\n    use super::types::*;\n");
+    assert_contains(&code, "pub mod server {\n    use super::types::*;\n");
+}
+
+#[test]
+fn combined_shared_types() {
+    let code = combined();
+    assert_contains(
+        &code,
+        "    /// D-Bus type `a(su)`.\n    pub type GetItemsItems = Vec<(String, u32)>;",
+    );
+    assert_contains(
+        &code,
+        "    pub type ChangedHints<'a> = std::collections::HashMap<String, zbus::zvariant::Value<'a>>;",
+    );
+    assert_not_contains(&code, "pub type CountValue");
+}
+
+#[test]
+fn combined_sides_use_shared_types() {
+    let code = combined();
+    // Client proxy.
+    assert_contains(&code, "fn get_items(&self) -> zbus::Result<GetItemsItems>;");
+    assert_contains(
+        &code,
+        "fn set_items(&self, items: GetItemsItems) -> zbus::Result<()>;",
+    );
+    assert_contains(
+        &code,
+        "fn changed(&self) -> zbus::Result<zbus::SignalStream<ChangedHints<'_>>>;",
+    );
+    // Delegate trait.
+    assert_contains(
+        &code,
+        "async fn get_items(&self) -> zbus::fdo::Result<GetItemsItems>;",
+    );
+    assert_contains(
+        &code,
+        "async fn set_items(&self, items: GetItemsItems) -> zbus::fdo::Result<()>;",
+    );
+}
+
+#[test]
+fn combined_without_shared_types() {
+    let xml = r#"<node><interface name="org.example.Bar"><method name="Ping"/></interface></node>"#;
+    let code = generate_from_xml(xml, Mode::Both, &Options::default());
+    assert_contains(&code, "pub mod types {}\n\npub mod client {");
+    assert_not_contains(&code, "use super::types::*;");
+}
+
+#[test]
+fn combined_type_overrides() {
+    let options = Options {
+        type_overrides: vec![("a{sv}".to_string(), "crate::Hints".to_string())]
+            .into_iter()
+            .collect(),
+        ..Options::default()
+    };
+    let code = generate_from_xml(XML, Mode::Both, &options);
+    assert_not_contains(&code, "pub type ChangedHints");
+    assert_contains(
+        &code,
+        "fn changed(&self) -> zbus::Result<zbus::SignalStream<crate::Hints>>;",
+    );
+}
+
+#[test]
+fn interface_modes() {
+    let xml = r#"
+<node>
+  <interface name="org.example.Foo"><method name="Ping"/></interface>
+  <interface name="org.example.Bar"><method name="Pong"/></interface>
+</node>
+"#;
+    let options = Options {
+        interface_modes: vec![("org.example.Bar".to_string(), Mode::Server)]
+            .into_iter()
+            .collect(),
+        ..Options::default()
+    };
+    let code = generate_from_xml(xml, Mode::Client, &options);
+    assert_contains(&code, "pub mod client {");
+    assert_contains(&code, "pub trait Foo {");
+    assert_not_contains(&code, "pub trait Bar {");
+    assert_contains(&code, "pub trait BarDelegate");
+    assert_not_contains(&code, "pub trait FooDelegate");
+
+    // Every interface in the same mode keeps the flat layout.
+    let options = Options {
+        interface_modes: vec![
+            ("org.example.Foo".to_string(), Mode::Server),
+            ("org.example.Bar".to_string(), Mode::Server),
+        ]
+        .into_iter()
+        .collect(),
+        ..Options::default()
+    };
+    let code = generate_from_xml(xml, Mode::Client, &options);
+    assert_not_contains(&code, "pub mod client {");
+    assert_contains(&code, "pub trait FooDelegate");
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "config")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use zbus_xml_gen::{Config, Error};
+
+mod common;
+use common::{assert_contains, assert_not_contains, temp_dir};
+
+const XML: &str = r#"
+<node>
+  <interface name="org.example.Foo1">
+    <method name="GetHints"><arg name="hints" type="a{sv}" direction="out"/></method>
+  </interface>
+  <interface name="org.example.Bar">
+    <method name="Ping"/>
+  </interface>
+  <interface name="org.freedesktop.DBus.Peer">
+    <method name="Ping"/>
+  </interface>
+</node>
+"#;
+
+/// A fresh directory holding `interfaces/foo.xml`.
+fn setup(name: &str) -> PathBuf {
+    temp_dir(name, &[("interfaces/foo.xml", XML)])
+}
+
+fn generate(dir: &Path, config: &str) -> Result<Vec<(String, String)>, Error> {
+    fs::write(dir.join("zbus-xml-gen.toml"), config).unwrap();
+    let config = Config::from_file(&dir.join("zbus-xml-gen.toml"))?;
+    let files = config.generate(dir)?;
+    Ok(files
+        .into_iter()
+        .map(|file| (file.name, file.code))
+        .collect())
+}
+
+#[test]
+fn config_file_output() {
+    let dir = setup("config_file_output");
+    let files = generate(
+        &dir,
+        r#"
+[[output]]
+inputs = ["interfaces"]
+file = "src/bindings.rs"
+exclude = ["org.freedesktop.DBus.*"]
+service = "org.example.Service"
+
+[output.types]
+"a{sv}" = "crate::Hints"
+
+[output.interfaces."org.example.Foo1"]
+rename = "Foo"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(files.len(), 1);
+    let (name, code) = &files[0];
+    assert_eq!(name, "src/bindings.rs");
+    assert_contains(code, "// Sources: interfaces/foo.xml\n");
+    assert_contains(
+        code,
+        r#"#[proxy(interface = "org.example.Foo1", default_service = "org.example.Service")]"#,
+    );
+    assert_contains(code, "pub trait Foo {");
+    assert_contains(code, "crate::Hints");
+    assert_contains(code, "pub trait Bar {");
+    assert_not_contains(code, "Peer");
+}
+
+#[test]
+fn config_out_dir_with_interface_modes() {
+    let dir = setup("config_out_dir");
+    let files = generate(
+        &dir,
+        r#"
+[[output]]
+inputs = ["interfaces/foo.xml"]
+out-dir = "src/bindings"
+include = ["org.example.*"]
+
+[output.interfaces."org.example.Bar"]
+mode = "server"
+"#,
+    )
+    .unwrap();
+
+    let names: Vec<_> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert!(names.contains(&"src/bindings/mod.rs"));
+    assert!(names.contains(&"src/bindings/client/org_example_foo1.rs"));
+    assert!(names.contains(&"src/bindings/server/org_example_bar.rs"));
+    assert!(!names.iter().any(|name| name.contains("peer")));
+}
+
+#[test]
+fn config_errors() {
+    let dir = setup("config_errors");
+    let err = generate(&dir, "[[output]]\ninputs = [\"interfaces\"]\n").unwrap_err();
+    assert!(matches!(err, Error::Config { .. }));
+    assert_contains(&err.to_string(), "either `out-dir` or `file`");
+
+    let err = generate(&dir, "[[output]]\nfile = \"a.rs\"\nserver = true\n").unwrap_err();
+    assert!(matches!(err, Error::Config { .. }));
+    assert_contains(&err.to_string(), "server");
+
+    let err = generate(
+        &dir,
+        "[[output]]\ninputs = [\"missing.xml\"]\nfile = \"a.rs\"\n",
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::Io { .. }));
+
+    // Errors name the configuration file actually read.
+    fs::write(
+        dir.join("ci.toml"),
+        "[[output]]\ninputs = [\"interfaces\"]\n",
+    )
+    .unwrap();
+    let config = Config::from_file(&dir.join("ci.toml")).unwrap();
+    match config.generate(&dir).unwrap_err() {
+        Error::Config { path, .. } => assert_eq!(path, dir.join("ci.toml")),
+        err => panic!("unexpected error: {}", err),
+    }
+}
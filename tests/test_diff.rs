@@ -0,0 +1,152 @@
+use zbus_xml_gen::{diff_xml, ChangeKind, Compatibility};
+
+const OLD: &str = r#"<node>
+  <interface name="org.example.Foo">
+    <method name="Get">
+      <arg name="key" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="Reset"/>
+    <method name="Rename">
+      <arg name="from" type="s" direction="in"/>
+    </method>
+    <signal name="Changed">
+      <arg name="key" type="s"/>
+    </signal>
+    <property name="Level" type="u" access="readwrite"/>
+    <property name="Mode" type="s" access="read"/>
+    <property name="Size" type="u" access="read"/>
+  </interface>
+  <interface name="org.example.Old"/>
+</node>
+"#;
+
+const NEW: &str = r#"<node>
+  <interface name="org.example.Foo">
+    <method name="Get">
+      <arg name="key" type="s" direction="in"/>
+      <arg name="flags" type="u" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="Rename">
+      <arg name="old_name" type="s" direction="in"/>
+      <annotation name="org.freedesktop.DBus.Deprecated" value="true"/>
+    </method>
+    <method name="Clear"/>
+    <signal name="Changed">
+      <arg name="key" type="s"/>
+      <arg name="value" type="v"/>
+    </signal>
+    <property name="Level" type="u" access="read"/>
+    <property name="Mode" type="s" access="readwrite"/>
+    <property name="Size" type="t" access="read"/>
+  </interface>
+  <interface name="org.example.New"/>
+</node>
+"#;
+
+#[test]
+fn diff_classifies_changes() {
+    let changes: Vec<_> = diff_xml(OLD, NEW)
+        .into_iter()
+        .map(|change| (change.compatibility, change.kind, change.member))
+        .collect();
+    let breaking = |kind, member: &str| (Compatibility::Breaking, kind, Some(member.to_string()));
+    let compatible =
+        |kind, member: &str| (Compatibility::Compatible, kind, Some(member.to_string()));
+    assert_eq!(
+        changes,
+        [
+            breaking(ChangeKind::MethodInArgsChanged, "Get"),
+            breaking(ChangeKind::MethodRemoved, "Reset"),
+            compatible(ChangeKind::ArgsRenamed, "Rename"),
+            compatible(ChangeKind::DeprecationChanged, "Rename"),
+            compatible(ChangeKind::MethodAdded, "Clear"),
+            breaking(ChangeKind::SignalArgsChanged, "Changed"),
+            breaking(ChangeKind::PropertyAccessChanged, "Level"),
+            compatible(ChangeKind::PropertyAccessChanged, "Mode"),
+            breaking(ChangeKind::PropertyTypeChanged, "Size"),
+            (Compatibility::Breaking, ChangeKind::InterfaceRemoved, None),
+            (Compatibility::Compatible, ChangeKind::InterfaceAdded, None),
+        ]
+    );
+}
+
+#[test]
+fn diff_messages() {
+    let changes = diff_xml(OLD, NEW);
+    assert_eq!(
+        changes[0].to_string(),
+        "breaking: org.example.Foo.Get: in-args added: flags: u"
+    );
+    assert_eq!(
+        changes[2].to_string(),
+        "compatible: org.example.Foo.Rename: in-args renamed: from to old_name"
+    );
+    assert_eq!(
+        changes[6].to_string(),
+        "breaking: org.example.Foo.Level: access changed from readwrite to read"
+    );
+    assert_eq!(
+        changes[8].to_string(),
+        "breaking: org.example.Foo.Size: type changed from u to t"
+    );
+    assert_eq!(
+        changes[9].to_string(),
+        "breaking: org.example.Old: interface removed"
+    );
+}
+
+#[test]
+fn diff_object_paths() {
+    let old = r#"<node>
+  <node name="/a"><interface name="org.example.Foo"/></node>
+</node>"#;
+    let new = r#"<node>
+  <node name="/b"><interface name="org.example.Foo"/></node>
+</node>"#;
+    let changes: Vec<_> = diff_xml(old, new)
+        .into_iter()
+        .map(|change| (change.compatibility, change.kind))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (Compatibility::Breaking, ChangeKind::ObjectPathRemoved),
+            (Compatibility::Compatible, ChangeKind::ObjectPathAdded),
+        ]
+    );
+}
+
+#[test]
+fn diff_emits_changed_signal() {
+    let xml = |value: &str| {
+        format!(
+            r#"<node><interface name="org.example.Foo">
+  <property name="P" type="u" access="read">
+    <annotation name="org.freedesktop.DBus.Property.EmitsChangedSignal" value="{}"/>
+  </property>
+</interface></node>"#,
+            value
+        )
+    };
+    let compatibility = |old: &str, new: &str| {
+        diff_xml(&xml(old), &xml(new))
+            .iter()
+            .map(|change| change.compatibility)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(compatibility("true", "true"), []);
+    assert_eq!(compatibility("true", "false"), [Compatibility::Breaking]);
+    assert_eq!(
+        compatibility("true", "invalidates"),
+        [Compatibility::Breaking]
+    );
+    assert_eq!(compatibility("false", "true"), [Compatibility::Compatible]);
+    assert_eq!(compatibility("true", "const"), [Compatibility::Compatible]);
+}
+
+#[test]
+fn diff_identical() {
+    assert!(diff_xml(OLD, OLD).is_empty());
+}
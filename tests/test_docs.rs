@@ -0,0 +1,119 @@
+use zbus_xml_gen::{generate_docs_from_xml, DocFormat, Options};
+
+mod common;
+use common::{assert_contains, assert_not_contains};
+
+const XML: &str = r#"
+<node name="/org/example/Foo">
+  <interface name="org.example.Foo">
+    <annotation name="org.freedesktop.DBus.Deprecated" value="true"/>
+    <method name="Get">
+      <arg name="key" type="s" direction="in"/>
+      <arg name="point" type="(ii)" direction="in"/>
+      <arg name="options" type="a{sv}" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <property name="Level" type="u" access="readwrite"/>
+    <property name="Name" type="s" access="read"/>
+    <signal name="Changed">
+      <arg name="key" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+#[test]
+fn docs_markdown() {
+    let docs = generate_docs_from_xml(XML, DocFormat::Markdown, &Options::default());
+    assert_contains(&docs, "# `org.example.Foo`\n");
+    assert_contains(
+        &docs,
+        "Rust: `Foo` (client proxy `FooProxy`), object path `/org/example/Foo`.",
+    );
+    assert_contains(&docs, "- `org.freedesktop.DBus.Deprecated` = `true`");
+    assert_contains(
+        &docs,
+        "### `Get(s key, (ii) point, a{sv} options) -> (v value)`",
+    );
+    assert_contains(&docs, "| `key` | in | `s` | `String` |");
+    assert_contains(&docs, "| `point` | in | `(ii)` | `(i32, i32)` |");
+    assert_contains(
+        &docs,
+        "busctl call SERVICE /org/example/Foo org.example.Foo Get 's(ii)a{sv}' \"key\" 0 0 0",
+    );
+    assert_contains(&docs, "`u` (`u32`), read-write.");
+    assert_contains(
+        &docs,
+        "busctl set-property SERVICE /org/example/Foo org.example.Foo Level u 0",
+    );
+    assert_not_contains(
+        &docs,
+        "set-property SERVICE /org/example/Foo org.example.Foo Name",
+    );
+    assert_contains(&docs, "| `key` | `s` | `String` |");
+    assert_contains(
+        &docs,
+        "busctl monitor SERVICE --match \"type='signal',interface='org.example.Foo',member='Changed'\"",
+    );
+}
+
+#[test]
+fn docs_html() {
+    let docs = generate_docs_from_xml(XML, DocFormat::Html, &Options::default());
+    assert!(docs.starts_with("<!DOCTYPE html>\n"));
+    assert!(docs.ends_with("</body>\n</html>\n"));
+    assert_contains(&docs, "<h1><code>org.example.Foo</code></h1>");
+    assert_contains(
+        &docs,
+        "<h3><code>Get(s key, (ii) point, a{sv} options) -&gt; (v value)</code></h3>",
+    );
+    assert_contains(
+        &docs,
+        "<tr><td><code>key</code></td><td>in</td><td><code>s</code></td><td><code>String</code></td></tr>",
+    );
+    assert_contains(&docs, "Get &apos;s(ii)a{sv}&apos; &quot;key&quot; 0 0 0");
+}
+
+#[test]
+fn docs_with_options() {
+    let mut options = Options {
+        default_service: Some("org.example".to_string()),
+        default_path: Some("/".to_string()),
+        ..Options::default()
+    };
+    options
+        .type_overrides
+        .insert("(ii)".to_string(), "Point".to_string());
+    options
+        .renames
+        .insert("org.example.Foo".to_string(), "Thing".to_string());
+    let docs = generate_docs_from_xml(XML, DocFormat::Markdown, &options);
+    assert_contains(
+        &docs,
+        "Rust: `Thing` (client proxy `ThingProxy`), object path `/`.",
+    );
+    assert_contains(&docs, "| `point` | in | `(ii)` | `Point` |");
+    assert_contains(
+        &docs,
+        "busctl get-property org.example / org.example.Foo Name",
+    );
+}
+
+#[test]
+fn docs_markdown_escapes_code_and_cells() {
+    let xml = r#"
+<node>
+  <interface name="org.example.Foo">
+    <annotation name="org.example.Pattern" value="a|b `c` ``d``"/>
+    <annotation name="org.example.Quote" value="`"/>
+    <method name="Get">
+      <arg name="a|b" type="s" direction="in"/>
+    </method>
+  </interface>
+</node>
+"#;
+    let docs = generate_docs_from_xml(xml, DocFormat::Markdown, &Options::default());
+    assert_contains(&docs, "- `org.example.Pattern` = ``` a|b `c` ``d`` ```\n");
+    assert_contains(&docs, "- `org.example.Quote` = `` ` ``\n");
+    assert_contains(&docs, "| `a\\|b` | in | `s` | `String` |");
+}
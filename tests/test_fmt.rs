@@ -0,0 +1,136 @@
+use zbus_xml_gen::{format_xml, XmlFormat};
+
+const XML: &str = r#"<?xml version="1.0"?>
+<!-- Copyright -->
+<node>
+    <interface name="org.example.Foo">
+    <property access="read" type="s" name="Name"/>
+        <!-- Looks up a value -->
+        <method name="Get"><arg type="s" name="key"/>
+        <arg direction="out" type="v" name="value"/></method>
+      <signal name="Changed"><arg direction="out" type="s" name="key"/></signal>
+      <method name="Clear"/>
+      <annotation value="true" name="org.freedesktop.DBus.Deprecated"/>
+    </interface>
+    <node name="b"/>
+    <interface name="org.example.Bar"/>
+</node>
+"#;
+
+#[test]
+fn format_canonical() {
+    assert_eq!(
+        format_xml(XML, &XmlFormat::default()).unwrap(),
+        r#"<?xml version="1.0"?>
+<!-- Copyright -->
+<node>
+  <interface name="org.example.Foo">
+    <property name="Name" type="s" access="read"/>
+    <!-- Looks up a value -->
+    <method name="Get">
+      <arg name="key" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <signal name="Changed">
+      <arg name="key" type="s"/>
+    </signal>
+    <method name="Clear"/>
+    <annotation name="org.freedesktop.DBus.Deprecated" value="true"/>
+  </interface>
+  <node name="b"/>
+  <interface name="org.example.Bar"/>
+</node>
+"#
+    );
+}
+
+#[test]
+fn format_sorted() {
+    let format = XmlFormat {
+        indent: 4,
+        sort: true,
+    };
+    assert_eq!(
+        format_xml(XML, &format).unwrap(),
+        r#"<?xml version="1.0"?>
+<!-- Copyright -->
+<node>
+    <interface name="org.example.Bar"/>
+    <interface name="org.example.Foo">
+        <annotation name="org.freedesktop.DBus.Deprecated" value="true"/>
+        <method name="Clear"/>
+        <!-- Looks up a value -->
+        <method name="Get">
+            <arg name="key" type="s" direction="in"/>
+            <arg name="value" type="v" direction="out"/>
+        </method>
+        <signal name="Changed">
+            <arg name="key" type="s"/>
+        </signal>
+        <property name="Name" type="s" access="read"/>
+    </interface>
+    <node name="b"/>
+</node>
+"#
+    );
+}
+
+#[test]
+fn format_is_idempotent() {
+    for sort in [false, true].iter() {
+        let format = XmlFormat {
+            sort: *sort,
+            ..XmlFormat::default()
+        };
+        let formatted = format_xml(XML, &format).unwrap();
+        assert_eq!(format_xml(&formatted, &format).unwrap(), formatted);
+    }
+}
+
+#[test]
+fn format_rejects_invalid_xml() {
+    assert!(format_xml("<node><interface>", &XmlFormat::default()).is_err());
+    assert!(format_xml(
+        r#"<node><interface name="a.B"><property name="P"/></interface></node>"#,
+        &XmlFormat::default()
+    )
+    .is_err());
+}
+
+#[test]
+fn format_keeps_doc_text() {
+    let xml = r#"<node xmlns:doc="http://www.freedesktop.org/dbus/1.0/doc.dtd">
+<interface name="org.example.Foo">
+<method name="Bar">
+<doc:doc>
+<doc:description>
+<doc:para>Does the bar &amp; <doc:ref type="method" to="Baz">baz</doc:ref>.</doc:para>
+<doc:para><![CDATA[Returns <nothing>.]]></doc:para>
+</doc:description>
+</doc:doc>
+</method>
+</interface>
+</node>
+"#;
+    let formatted = format_xml(xml, &XmlFormat::default()).unwrap();
+    assert_eq!(
+        formatted,
+        r#"<node xmlns:doc="http://www.freedesktop.org/dbus/1.0/doc.dtd">
+  <interface name="org.example.Foo">
+    <method name="Bar">
+      <doc:doc>
+        <doc:description>
+          <doc:para>Does the bar &amp; <doc:ref type="method" to="Baz">baz</doc:ref>.</doc:para>
+          <doc:para>Returns &lt;nothing&gt;.</doc:para>
+        </doc:description>
+      </doc:doc>
+    </method>
+  </interface>
+</node>
+"#
+    );
+    assert_eq!(
+        format_xml(&formatted, &XmlFormat::default()).unwrap(),
+        formatted
+    );
+}
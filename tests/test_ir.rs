@@ -0,0 +1,89 @@
+#![cfg(feature = "json")]
+
+use zbus_xml_gen::{ir_from_xml, IrType, Options, IR_SCHEMA_VERSION};
+
+const XML: &str = r#"
+<node name="/org/example/Foo">
+  <interface name="org.example.Foo">
+    <annotation name="org.freedesktop.DBus.Deprecated" value="true"/>
+    <method name="Get">
+      <arg name="type" type="s" direction="in"/>
+      <arg name="options" type="a{s(iav)}" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <property name="Get" type="u" access="readwrite"/>
+    <signal name="Changed">
+      <arg name="key" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+#[test]
+fn ir_names() {
+    let ir = ir_from_xml(XML, &Options::default());
+    assert_eq!(ir.schema_version, IR_SCHEMA_VERSION);
+    let iface = &ir.interfaces[0];
+    assert_eq!(iface.name, "org.example.Foo");
+    assert_eq!(iface.rust_name, "Foo");
+    assert_eq!(iface.proxy_name, "FooProxy");
+    assert_eq!(iface.paths, ["/org/example/Foo"]);
+    assert_eq!(iface.annotations[0].name, "org.freedesktop.DBus.Deprecated");
+
+    let method = &iface.methods[0];
+    assert_eq!(method.rust_name, "get");
+    assert_eq!(method.args[0].rust_name, "type_");
+    assert_eq!(method.args[2].direction, "out");
+    assert_eq!(method.args[2].rust_type, "zbus::zvariant::Value<'_>");
+
+    let prop = &iface.properties[0];
+    assert_eq!(prop.rust_name, "get_prop");
+    assert_eq!(prop.rust_setter.as_deref(), Some("set_get_prop"));
+    assert_eq!(prop.access, "readwrite");
+    assert_eq!(iface.signals[0].rust_name, "changed");
+}
+
+#[test]
+fn ir_type_tree() {
+    let basic = |signature: &str| IrType::Basic {
+        signature: signature.to_string(),
+    };
+    assert_eq!(
+        IrType::parse("a{s(iav)}"),
+        IrType::Dict {
+            signature: "a{s(iav)}".to_string(),
+            key: Box::new(basic("s")),
+            value: Box::new(IrType::Struct {
+                signature: "(iav)".to_string(),
+                fields: vec![
+                    basic("i"),
+                    IrType::Array {
+                        signature: "av".to_string(),
+                        element: Box::new(IrType::Variant {
+                            signature: "v".to_string()
+                        }),
+                    },
+                ],
+            }),
+        }
+    );
+}
+
+#[test]
+fn ir_json() {
+    let mut options = Options::default();
+    options
+        .renames
+        .insert("org.example.Foo".to_string(), "Bar".to_string());
+    let json = ir_from_xml(XML, &options).to_json();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["schema_version"], IR_SCHEMA_VERSION);
+    let iface = &value["interfaces"][0];
+    assert_eq!(iface["rust_name"], "Bar");
+    assert_eq!(
+        iface["methods"][0]["args"][1]["type"]["value"]["kind"],
+        "struct"
+    );
+    assert_eq!(iface["properties"][0]["type"]["kind"], "basic");
+    assert_eq!(iface["signals"][0]["args"][0]["name"], "key");
+}
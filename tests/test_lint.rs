@@ -0,0 +1,103 @@
+use zbus_xml_gen::{lint_xml, Level, LintLevels, Rule};
+
+const XML: &str = r#"<node>
+  <interface name="org.example.Foo">
+    <method name="get_thing">
+      <arg name="type" type="s"/>
+      <arg type="u" direction="out"/>
+    </method>
+    <signal name="Changed">
+      <arg name="value" type="s" direction="out"/>
+    </signal>
+    <property name="Level" type="u" access="readwrite">
+      <annotation name="org.freedesktop.DBus.Property.EmitChangedSignal" value="false"/>
+    </property>
+    <method name="SetLevel"/>
+  </interface>
+  <interface name="org.other.Foo"/>
+</node>
+"#;
+
+fn rules(xml: &str, levels: &LintLevels) -> Vec<(Rule, Option<usize>)> {
+    lint_xml(xml, levels)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.rule, diagnostic.line))
+        .collect()
+}
+
+#[test]
+fn lint_conventions() {
+    assert_eq!(
+        rules(XML, &LintLevels::default()),
+        [
+            (Rule::MemberCase, Some(3)),
+            (Rule::MissingDirection, Some(4)),
+            (Rule::KeywordName, Some(4)),
+            (Rule::MissingArgName, Some(5)),
+            (Rule::SignalDirection, Some(8)),
+            (Rule::UnknownAnnotation, Some(11)),
+            (Rule::NameCollision, Some(13)),
+            (Rule::NameCollision, Some(15)),
+        ]
+    );
+}
+
+#[test]
+fn lint_messages() {
+    let diagnostics = lint_xml(XML, &LintLevels::default());
+    assert_eq!(
+        diagnostics[5].to_string(),
+        "warning[unknown-annotation]: unknown annotation \
+         'org.freedesktop.DBus.Property.EmitChangedSignal'; did you mean \
+         'org.freedesktop.DBus.Property.EmitsChangedSignal'?"
+    );
+    assert_eq!(
+        diagnostics[6].message,
+        "method 'org.example.Foo.SetLevel' collides with another member as `set_level`"
+    );
+}
+
+#[test]
+fn lint_levels() {
+    let mut levels = LintLevels::default();
+    levels.set("all", Level::Allow).unwrap();
+    levels.set("missing-arg-name", Level::Deny).unwrap();
+    let diagnostics = lint_xml(XML, &levels);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].level, Level::Deny);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "error[missing-arg-name]: argument 1 of method 'org.example.Foo.get_thing' has no name"
+    );
+}
+
+#[test]
+fn lint_invalid_data() {
+    let levels = LintLevels::default();
+    assert_eq!(
+        rules("<node>\n<interface name=\"Foo\"/>\n</node>", &levels),
+        [(Rule::InvalidName, Some(2))]
+    );
+    assert_eq!(
+        rules("<node>\n<interface>\n</node>", &levels),
+        [(Rule::InvalidXml, Some(3))]
+    );
+    assert_eq!(rules("<nodes/>", &levels), [(Rule::InvalidXml, Some(1))]);
+    // Rejected by `zbus_xml` only.
+    let bad_signature = r#"<node><interface name="a.B"><property name="P" type="a" access="read"/></interface></node>"#;
+    assert_eq!(rules(bad_signature, &levels), [(Rule::InvalidXml, None)]);
+}
+
+#[test]
+fn lint_clean_file() {
+    let xml = r#"<node>
+  <interface name="org.example.Foo">
+    <method name="GetThing"><arg name="id" type="s" direction="in"/></method>
+    <signal name="Changed"><arg name="value" type="s"/></signal>
+    <property name="Level" type="u" access="read">
+      <annotation name="org.freedesktop.DBus.Property.EmitsChangedSignal" value="true"/>
+    </property>
+  </interface>
+</node>"#;
+    assert!(lint_xml(xml, &LintLevels::default()).is_empty());
+}
@@ -0,0 +1,137 @@
+use std::fs;
+
+use zbus_xml_gen::{generate_modules_from_xml, GeneratedFile, Mode, Options};
+
+mod common;
+use common::{assert_contains, assert_not_contains, temp_dir};
+
+const XML: &str = r#"
+<node name="/org/example">
+  <interface name="org.example.Complex">
+    <method name="Ping"><arg name="x" type="i" direction="in"/></method>
+  </interface>
+  <node name="child">
+    <interface name="org.example.Second">
+      <property name="Level" type="u" access="read"/>
+    </interface>
+  </node>
+</node>
+"#;
+
+fn file<'a>(files: &'a [GeneratedFile], name: &str) -> &'a str {
+    &files
+        .iter()
+        .find(|f| f.name == name)
+        .unwrap_or_else(|| panic!("no {} generated", name))
+        .code
+}
+
+#[test]
+fn modules_one_file_per_interface() {
+    let files = generate_modules_from_xml(XML, Mode::Client, &Options::default());
+    let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["mod.rs", "org_example_complex.rs", "org_example_second.rs"]
+    );
+
+    let complex = file(&files, "org_example_complex.rs");
+    assert_contains(complex, "use super::*;");
+    assert_contains(complex, "pub trait Complex {");
+    assert_not_contains(complex, "pub trait Second {");
+}
+
+#[test]
+fn modules_root_holds_imports_once() {
+    let files = generate_modules_from_xml(XML, Mode::Server, &Options::default());
+    let root = file(&files, "mod.rs");
+    assert_contains(root, "use zbus::{interface, Result};");
+    assert_contains(
+        root,
+        "mod org_example_second;\npub use self::org_example_second::*;",
+    );
+    for f in &files[1..] {
+        assert_not_contains(&f.code, "use zbus::");
+    }
+}
+
+#[test]
+fn modules_root_holds_serve() {
+    let options = Options {
+        serve: true,
+        ..Options::default()
+    };
+    let files = generate_modules_from_xml(XML, Mode::Server, &options);
+    assert_contains(file(&files, "mod.rs"), "pub fn serve<'a>(");
+    assert_not_contains(file(&files, "org_example_complex.rs"), "pub fn serve");
+}
+
+#[test]
+fn write_to_skips_unchanged_files() {
+    let dir = temp_dir("write_to", &[]);
+    let file = GeneratedFile {
+        name: "mod.rs".to_string(),
+        code: "pub struct Foo;\n".to_string(),
+    };
+
+    assert!(file.write_to(&dir).unwrap());
+    assert!(!file.write_to(&dir).unwrap());
+    assert_eq!(fs::read_to_string(dir.join("mod.rs")).unwrap(), file.code);
+}
+
+#[test]
+fn modules_both_sides() {
+    let files = generate_modules_from_xml(XML, Mode::Both, &Options::default());
+    let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "mod.rs",
+            "types.rs",
+            "client/mod.rs",
+            "client/org_example_complex.rs",
+            "client/org_example_second.rs",
+            "server/mod.rs",
+            "server/org_example_complex.rs",
+            "server/org_example_second.rs",
+        ]
+    );
+    assert_eq!(
+        file(&files, "mod.rs"),
+        "pub mod types;\npub mod client;\npub mod server;\n"
+    );
+    assert_contains(file(&files, "client/mod.rs"), "use zbus::proxy;");
+    assert_contains(
+        file(&files, "server/mod.rs"),
+        "use zbus::{interface, Result};",
+    );
+}
+
+#[test]
+fn modules_name_constants() {
+    let options = Options {
+        names: true,
+        ..Options::default()
+    };
+    let files = generate_modules_from_xml(XML, Mode::Both, &options);
+    assert_contains(file(&files, "mod.rs"), "pub mod names;");
+    let names = file(&files, "names.rs");
+    assert_contains(names, "pub mod complex {");
+    assert_contains(names, "pub mod second {");
+    assert_contains(names, "MemberName::from_static_str_unchecked(\"Ping\")");
+}
+
+#[test]
+fn modules_allow_attributes() {
+    let options = Options {
+        allow: vec!["dead_code".to_string(), "clippy::all".to_string()],
+        ..Options::default()
+    };
+    for file in generate_modules_from_xml(XML, Mode::Both, &options) {
+        assert!(
+            file.code.starts_with("#![allow(dead_code, clippy::all)]\n"),
+            "{} lacks allow attributes",
+            file.name
+        );
+    }
+}
@@ -0,0 +1,149 @@
+#![cfg(feature = "reverse")]
+
+use zbus_xml_gen::{
+    diff_xml, generate_from_xml, generate_xml_from_rust, Compatibility, Mode, Options,
+};
+
+const PROXY: &str = r#"
+use zbus::{proxy, Result};
+
+#[proxy(interface = "org.example.Foo", default_service = "org.example")]
+pub trait Foo {
+    #[zbus(out_args("value", "count"))]
+    fn get_thing(&self, key: &str, opts: HashMap<&str, Value<'_>>) -> Result<(OwnedValue, u32)>;
+
+    #[zbus(name = "reset_all", no_reply)]
+    fn reset(&self) -> Result<()>;
+
+    #[zbus(out_args("point"))]
+    fn locate(&self) -> Result<(i32, i32)>;
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn level(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn set_level(&self, value: u32) -> Result<()>;
+
+    #[zbus(signal)]
+    fn changed(&self, r#type: &str, path: ObjectPath<'_>) -> Result<()>;
+}
+
+pub trait NotAProxy {
+    fn ignored(&self);
+}
+"#;
+
+#[test]
+fn reverse_proxy() {
+    assert_eq!(
+        generate_xml_from_rust(PROXY, &Options::default()).unwrap(),
+        r#"<node>
+  <interface name="org.example.Foo">
+    <method name="GetThing">
+      <arg name="key" type="s" direction="in"/>
+      <arg name="opts" type="a{sv}" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+      <arg name="count" type="u" direction="out"/>
+    </method>
+    <method name="reset_all">
+      <annotation name="org.freedesktop.DBus.Method.NoReply" value="true"/>
+    </method>
+    <method name="Locate">
+      <arg name="point" type="(ii)" direction="out"/>
+    </method>
+    <property name="Level" type="u" access="readwrite">
+      <annotation name="org.freedesktop.DBus.Property.EmitsChangedSignal" value="const"/>
+    </property>
+    <signal name="Changed">
+      <arg name="type" type="s"/>
+      <arg name="path" type="o"/>
+    </signal>
+  </interface>
+</node>
+"#
+    );
+}
+
+#[test]
+fn reverse_interface() {
+    let code = r#"
+mod server {
+    pub struct Bar;
+
+    #[zbus::interface(name = "org.example.Bar")]
+    impl Bar {
+        async fn frobnicate(
+            &self,
+            items: Vec<(String, u32)>,
+            #[zbus(header)] header: Header<'_>,
+        ) -> zbus::fdo::Result<Vec<u8>> {
+            Ok(Vec::new())
+        }
+
+        #[zbus(property)]
+        async fn secret(&self) -> Point {
+            Point(0, 0)
+        }
+
+        #[zbus(signal)]
+        async fn done(emitter: &SignalEmitter<'_>, ok: bool) -> zbus::Result<()>;
+    }
+}
+"#;
+    let mut options = Options::default();
+    options
+        .type_overrides
+        .insert("(ii)".to_string(), "Point".to_string());
+    assert_eq!(
+        generate_xml_from_rust(code, &options).unwrap(),
+        r#"<node>
+  <interface name="org.example.Bar">
+    <method name="Frobnicate">
+      <arg name="items" type="a(su)" direction="in"/>
+      <arg type="ay" direction="out"/>
+    </method>
+    <property name="Secret" type="(ii)" access="read"/>
+    <signal name="Done">
+      <arg name="ok" type="b"/>
+    </signal>
+  </interface>
+</node>
+"#
+    );
+
+    let err = generate_xml_from_rust(code, &Options::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot map `Point` to a D-Bus type; add a type override"
+    );
+}
+
+#[test]
+fn reverse_round_trips_generated_code() {
+    let xml = r#"
+<node>
+  <interface name="org.example.Foo">
+    <method name="GetThing">
+      <arg name="key" type="s" direction="in"/>
+      <arg name="opts" type="a{sv}" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+      <arg name="count" type="u" direction="out"/>
+    </method>
+    <property name="Level" type="u" access="readwrite"/>
+    <property name="Paths" type="ao" access="read"/>
+    <signal name="Changed">
+      <arg name="key" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+    for mode in [Mode::Client, Mode::Server].iter().copied() {
+        let code = generate_from_xml(xml, mode, &Options::default());
+        let back = generate_xml_from_rust(&code, &Options::default()).unwrap();
+        let breaking: Vec<_> = diff_xml(xml, &back)
+            .into_iter()
+            .filter(|change| change.compatibility == Compatibility::Breaking)
+            .collect();
+        assert!(breaking.is_empty(), "{:?}: {:?}", mode, breaking);
+    }
+}
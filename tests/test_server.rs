@@ -1,4 +1,9 @@
-use zbus_xml_gen::generate_server_interface_from_xml;
+use zbus_xml_gen::{
+    generate_server_interface_from_xml, generate_server_interface_with_options, ContextParams,
+    Dispatch, Options,
+};
+
+mod common;
 
 const XML: &str = r#"
 <node>
@@ -84,11 +89,14 @@ const XML: &str = r#"
 
 macro_rules! tests {
     ([ $( ($name:ident, $expected:expr) ),* $(,)? ]) => {
+        tests!(generate_server_interface_from_xml(XML), [ $( ($name, $expected) ),* ]);
+    };
+    ($generate:expr, [ $( ($name:ident, $expected:expr) ),* $(,)? ]) => {
         $(
             #[test]
             fn $name() {
-                let actual = generate_server_interface_from_xml(XML);
-                if !actual.contains($expected) {
+                let actual = $generate;
+                if !common::squeeze(&actual).contains(&common::squeeze($expected)) {
                     println!("\n=== GENERATED OUTPUT ===\n{}\n=========================", actual);
                     panic!("Assertion failed: expected snippet not found:\n{}", $expected);
                 }
@@ -145,3 +153,338 @@ tests!([
     (server_property_pi_d, "async fn pi(&self) -> f64;"),
     (server_property_dict_prop, "async fn dict_prop(&self) -> std::collections::HashMap<String, String>;"),
 ]);
+
+fn static_dispatch() -> Options {
+    Options {
+        dispatch: Dispatch::Static,
+        ..Options::default()
+    }
+}
+
+// Static dispatch
+tests!(generate_server_interface_with_options(XML, &static_dispatch()), [
+    (static_imports_once, "use zbus::{interface, Result};\nuse zbus::object_server::SignalEmitter;\n\npub trait ComplexDelegate"),
+    (static_trait_decl, "pub trait ComplexDelegate: Send + Sync + 'static {"),
+    (static_trait_method_with_inputs, "fn with_inputs(&self, key: String, flag: bool) -> impl std::future::Future<Output = zbus::fdo::Result<i32>> + Send;"),
+    (static_trait_property_getter, "fn status(&self) -> impl std::future::Future<Output = u32> + Send;"),
+    (static_trait_property_setter, "fn set_enabled(&self, val: bool) -> impl std::future::Future<Output = Result<()>> + Send;"),
+    (static_struct_decl, "pub struct Complex<D: ComplexDelegate> {\n    pub delegate: D,\n}"),
+    (static_struct_new_fn, "impl<D: ComplexDelegate> Complex<D> {\n    pub fn new(delegate: D) -> Self {"),
+    (static_interface_impl, "#[interface(name = \"org.example.Complex\")]\nimpl<D: ComplexDelegate> Complex<D> {"),
+    (static_delegate_call, "self.delegate.with_inputs(key, flag).await"),
+]);
+
+#[test]
+fn static_dispatch_has_no_async_trait() {
+    let actual = generate_server_interface_with_options(XML, &static_dispatch());
+    common::assert_not_contains(&actual, "async_trait");
+    common::assert_not_contains(&actual, "Arc<dyn");
+}
+
+#[test]
+fn dynamic_dispatch_imports_once() {
+    let actual = generate_server_interface_from_xml(XML);
+    assert_eq!(actual.matches("use async_trait::async_trait;").count(), 1);
+}
+
+fn with_signals() -> Options {
+    Options {
+        signals: true,
+        ..Options::default()
+    }
+}
+
+// Signal emission handle
+tests!(generate_server_interface_with_options(XML, &with_signals()), [
+    (signals_handle_decl, "pub struct ComplexSignalHandle {\n    emitter: SignalEmitter<'static>,\n}"),
+    (signals_handle_new, "pub fn new<P>(connection: &zbus::Connection, path: P) -> Result<Self>"),
+    (signals_handle_from_interface_ref, "pub fn from_interface_ref(iface: &zbus::object_server::InterfaceRef<Complex>) -> Self {"),
+    (signals_handle_state_changed, "pub async fn state_changed(&self, state: i32, error: &str) -> Result<()> {"),
+    (signals_handle_state_changed_emit, ".emit(\"org.example.Complex\", \"StateChanged\", &(state, error))"),
+    (signals_handle_items_updated, "pub async fn items_updated(&self, items: &[(String, String, String, std::collections::HashMap<String, String>, u16)]) -> Result<()> {"),
+    (signals_handle_items_updated_emit, ".emit(\"org.example.Complex\", \"ItemsUpdated\", &(items,))"),
+    (signals_handle_simple_signal, "pub async fn simple_signal(&self) -> Result<()> {"),
+    (signals_handle_simple_signal_emit, ".emit(\"org.example.Second\", \"SimpleSignal\", &())"),
+]);
+
+#[test]
+fn signals_handle_static_dispatch_is_generic() {
+    let options = Options {
+        dispatch: Dispatch::Static,
+        signals: true,
+        ..Options::default()
+    };
+    let actual = generate_server_interface_with_options(XML, &options);
+    common::assert_contains(
+        &common::squeeze(&actual),
+        &common::squeeze("pub fn from_interface_ref<D: ComplexDelegate>(iface: &zbus::object_server::InterfaceRef<Complex<D>>) -> Self {"),
+    );
+}
+
+#[test]
+fn signals_handle_skipped_without_signals() {
+    let xml =
+        r#"<node><interface name="org.example.Quiet"><method name="Ping"/></interface></node>"#;
+    let actual = generate_server_interface_with_options(xml, &with_signals());
+    common::assert_not_contains(&actual, "SignalHandle");
+}
+
+#[test]
+fn signals_handle_off_by_default() {
+    let actual = generate_server_interface_from_xml(XML);
+    common::assert_not_contains(&actual, "SignalHandle");
+}
+
+fn with_notifier() -> Options {
+    Options {
+        notifier: true,
+        ..Options::default()
+    }
+}
+
+// Property change notification
+tests!(generate_server_interface_with_options(XML, &with_notifier()), [
+    (notifier_property_enum, "pub enum ComplexProperty {\n    Status,\n    Enabled,\n    Status2,"),
+    (notifier_property_name, "Self::Status2 => \"status\","),
+    (notifier_decl, "pub struct ComplexNotifier {\n    iface: zbus::object_server::InterfaceRef<Complex>,\n}"),
+    (notifier_new, "pub fn new(iface: zbus::object_server::InterfaceRef<Complex>) -> Self {"),
+    (notifier_number_changed, "pub async fn number_changed(&self) -> Result<()> {\n        let iface = self.iface.get().await;\n        iface.number_changed(self.iface.signal_emitter()).await\n    }"),
+    (notifier_invalidate, "pub async fn invalidate(&self, properties: &[ComplexProperty]) -> Result<()> {"),
+    (notifier_invalidate_interface, "zbus::names::InterfaceName::from_static_str_unchecked(\"org.example.Complex\")"),
+    (notifier_second_property_enum, "pub enum SecondProperty {\n    DictProp,\n}"),
+]);
+
+#[test]
+fn notifier_skips_write_only_properties() {
+    let actual = generate_server_interface_with_options(XML, &with_notifier());
+    common::assert_not_contains(&actual, "fn secret_changed");
+}
+
+#[test]
+fn notifier_static_dispatch_is_generic() {
+    let options = Options {
+        dispatch: Dispatch::Static,
+        notifier: true,
+        ..Options::default()
+    };
+    let actual = generate_server_interface_with_options(XML, &options);
+    common::assert_contains(
+        &actual,
+        "pub struct ComplexNotifier<D: ComplexDelegate> {\n    iface: zbus::object_server::InterfaceRef<Complex<D>>,\n}",
+    );
+    common::assert_contains(&actual, "impl<D: ComplexDelegate> ComplexNotifier<D> {");
+}
+
+fn with_context() -> Options {
+    let mut options = Options {
+        context: ContextParams {
+            header: true,
+            ..ContextParams::default()
+        },
+        ..Options::default()
+    };
+    options.method_context.insert(
+        "org.example.Second.Ping".to_string(),
+        ContextParams {
+            connection: true,
+            object_server: true,
+            ..ContextParams::default()
+        },
+    );
+    options
+}
+
+// Context injection
+tests!(generate_server_interface_with_options(XML, &with_context()), [
+    (context_struct_decl, "pub struct ComplexContext<'a> {\n    pub header: Option<zbus::message::Header<'a>>,\n    pub connection: Option<&'a zbus::Connection>,\n    pub object_server: Option<&'a zbus::ObjectServer>,\n    pub emitter: Option<SignalEmitter<'a>>,\n}"),
+    (context_trait_method, "async fn with_inputs(&self, ctx: ComplexContext<'_>, key: String, flag: bool) -> zbus::fdo::Result<i32>;"),
+    (context_trait_method_no_args, "async fn get_nothing(&self, ctx: ComplexContext<'_>) -> zbus::fdo::Result<()>;"),
+    (context_interface_method, "async fn with_inputs(&self, key: String, flag: bool, #[zbus(header)] zbus_header: zbus::message::Header<'_>) -> zbus::fdo::Result<i32> {"),
+    (context_interface_build, "let ctx = ComplexContext {\n            header: Some(zbus_header),\n            connection: None,\n            object_server: None,\n            emitter: None,\n        };\n        self.delegate.with_inputs(ctx, key, flag).await"),
+    (context_per_method, "async fn ping(&self, input: String, #[zbus(header)] zbus_header: zbus::message::Header<'_>, #[zbus(connection)] zbus_connection: &zbus::Connection, #[zbus(object_server)] zbus_object_server: &zbus::ObjectServer) -> zbus::fdo::Result<String> {"),
+    (context_per_method_build, "self.delegate.ping(ctx, input).await"),
+]);
+
+const ANNOTATED_XML: &str = r#"
+<node>
+  <interface name="org.example.Annotated">
+    <method name="Authorize">
+      <annotation name="org.zbus.xmlgen.Context" value="header,signal_emitter"/>
+      <arg name="user" type="s" direction="in"/>
+    </method>
+    <method name="Plain"/>
+  </interface>
+</node>
+"#;
+
+// Context annotation
+tests!(generate_server_interface_from_xml(ANNOTATED_XML), [
+    (context_annotation_trait, "async fn authorize(&self, ctx: AnnotatedContext<'_>, user: String) -> zbus::fdo::Result<()>;"),
+    (context_annotation_interface, "async fn authorize(&self, user: String, #[zbus(header)] zbus_header: zbus::message::Header<'_>, #[zbus(signal_emitter)] zbus_emitter: SignalEmitter<'_>) -> zbus::fdo::Result<()> {"),
+    (context_annotation_other_method_unchanged, "async fn plain(&self) -> zbus::fdo::Result<()> {\n        self.delegate.plain().await"),
+]);
+
+#[test]
+fn context_struct_omitted_without_context() {
+    let actual = generate_server_interface_from_xml(XML);
+    common::assert_not_contains(&actual, "Context<'a>");
+}
+
+const NESTED_XML: &str = r#"
+<node name="/org/example">
+  <interface name="org.example.Complex">
+    <method name="Ping"/>
+  </interface>
+  <node name="child">
+    <interface name="org.example.Second">
+      <method name="Pong"/>
+    </interface>
+    <node name="again">
+      <interface name="org.example.Complex">
+        <method name="Ping"/>
+      </interface>
+    </node>
+  </node>
+</node>
+"#;
+
+fn with_serve() -> Options {
+    Options {
+        serve: true,
+        ..Options::default()
+    }
+}
+
+// Object server registration
+tests!(generate_server_interface_with_options(NESTED_XML, &with_serve()), [
+    (serve_child_interface_generated, "pub trait SecondDelegate: Send + Sync"),
+    (serve_delegates_decl, "pub struct Delegates {\n    pub complex: Arc<dyn ComplexDelegate>,\n    pub second: Arc<dyn SecondDelegate>,\n}"),
+    (serve_fn_decl, "pub fn serve<'a>(\n    mut builder: zbus::connection::Builder<'a>,\n    delegates: Delegates,\n    name: Option<&'a str>,\n) -> Result<zbus::connection::Builder<'a>> {"),
+    (serve_root_path, "builder = builder.serve_at(\"/org/example\", Complex::new(delegates.complex.clone()))?;"),
+    (serve_child_path, "builder = builder.serve_at(\"/org/example/child\", Second::new(delegates.second))?;"),
+    (serve_grandchild_path, "builder = builder.serve_at(\"/org/example/child/again\", Complex::new(delegates.complex))?;"),
+    (serve_request_name, "if let Some(name) = name {\n        builder = builder.name(name)?;\n    }"),
+]);
+
+#[test]
+fn serve_generates_each_interface_once() {
+    let actual = generate_server_interface_with_options(NESTED_XML, &with_serve());
+    assert_eq!(actual.matches("pub trait ComplexDelegate").count(), 1);
+}
+
+#[test]
+fn serve_static_dispatch_is_generic() {
+    let options = Options {
+        dispatch: Dispatch::Static,
+        serve: true,
+        ..Options::default()
+    };
+    let actual = generate_server_interface_with_options(NESTED_XML, &options);
+    common::assert_contains(
+        &actual,
+        "pub struct Delegates<ComplexD: ComplexDelegate + Clone, SecondD: SecondDelegate> {\n    pub complex: ComplexD,\n    pub second: SecondD,\n}",
+    );
+    common::assert_contains(
+        &actual,
+        "pub fn serve<'a, ComplexD: ComplexDelegate + Clone, SecondD: SecondDelegate>(",
+    );
+}
+
+fn with_mock() -> Options {
+    Options {
+        mock: true,
+        ..Options::default()
+    }
+}
+
+// Mock delegate
+tests!(generate_server_interface_with_options(XML, &with_mock()), [
+    (mock_call_enum, "pub enum ComplexDelegateCall {\n    GetItems,"),
+    (mock_call_variant_with_fields, "    WithInputs { key: String, flag: bool },"),
+    (mock_call_variant_keyword, "    Match { value: String },"),
+    (mock_call_variant_setter, "    SetEnabled { val: bool },"),
+    (mock_struct_decl, "#[derive(Default)]\npub struct MockComplexDelegate {\n    calls: std::sync::Mutex<Vec<ComplexDelegateCall>>,"),
+    (mock_closure_field, "    with_inputs: Option<Box<dyn Fn(String, bool) -> zbus::fdo::Result<i32> + Send + Sync>>,"),
+    (mock_calls_fn, "pub fn calls(&self) -> Vec<ComplexDelegateCall> {"),
+    (mock_expect_fn, "pub fn expect_with_inputs(mut self, f: impl Fn(String, bool) -> zbus::fdo::Result<i32> + Send + Sync + 'static) -> Self {"),
+    (mock_expect_keyword, "pub fn expect_match(mut self, f: impl Fn(String) -> zbus::fdo::Result<String> + Send + Sync + 'static) -> Self {"),
+    (mock_trait_impl, "#[async_trait]\nimpl ComplexDelegate for MockComplexDelegate {"),
+    (mock_records_call, "self.calls.lock().unwrap().push(ComplexDelegateCall::WithInputs { key: key.clone(), flag });"),
+    (mock_calls_closure, "Some(f) => f(key, flag),"),
+    (mock_unexpected_call, "None => panic!(\"unexpected call to MockComplexDelegate::with_inputs\")"),
+    (mock_property_getter, "async fn pi(&self) -> f64 {\n        self.calls.lock().unwrap().push(ComplexDelegateCall::Pi);"),
+]);
+
+#[test]
+fn mock_records_borrowed_args_as_debug() {
+    let xml = r#"
+<node>
+  <interface name="org.example.Variant">
+    <method name="Put"><arg name="v" type="v" direction="in"/></method>
+  </interface>
+</node>
+"#;
+    let actual = generate_server_interface_with_options(xml, &with_mock());
+    common::assert_contains(&actual, "    Put { v: String },");
+    common::assert_contains(
+        &common::squeeze(&actual),
+        &common::squeeze("VariantDelegateCall::Put { v: format!(\"{:?}\", v) }"),
+    );
+}
+
+#[test]
+fn mock_ignores_context() {
+    let options = Options {
+        mock: true,
+        ..with_context()
+    };
+    let actual = generate_server_interface_with_options(XML, &options);
+    common::assert_contains(
+        &actual,
+        "async fn get_nothing(&self, _ctx: ComplexContext<'_>) -> zbus::fdo::Result<()> {",
+    );
+}
+
+fn with_default_bodies() -> Options {
+    Options {
+        default_bodies: true,
+        ..Options::default()
+    }
+}
+
+// Default delegate method bodies
+tests!(generate_server_interface_with_options(XML, &with_default_bodies()), [
+    (default_body_method, "async fn with_inputs(&self, _key: String, _flag: bool) -> zbus::fdo::Result<i32> {\n        Err(zbus::fdo::Error::NotSupported(\"org.example.Complex.WithInputs is not supported\".into()))\n    }"),
+    (default_body_property_getter, "async fn pi(&self) -> zbus::fdo::Result<f64> {\n        Err(zbus::fdo::Error::UnknownProperty(\"org.example.Complex.pi\".into()))\n    }"),
+    (default_body_property_setter, "async fn set_secret(&self, _val: String) -> Result<()> {\n        Err(zbus::fdo::Error::UnknownProperty(\"org.example.Complex.secret\".into()).into())\n    }"),
+    (default_body_interface_getter, "#[zbus(property)]\n    async fn pi(&self) -> zbus::fdo::Result<f64> {\n        self.delegate.pi().await\n    }"),
+    (default_body_interface_setter, "#[zbus(property)]\n    async fn set_secret(&mut self, val: String) -> zbus::fdo::Result<()> {\n        self.delegate.set_secret(val).await.map_err(Into::into)\n    }"),
+]);
+
+#[test]
+fn default_bodies_static_dispatch() {
+    let options = Options {
+        dispatch: Dispatch::Static,
+        default_bodies: true,
+        ..Options::default()
+    };
+    let actual = generate_server_interface_with_options(XML, &options);
+    common::assert_contains(
+        &common::squeeze(&actual),
+        &common::squeeze("fn get_nothing(&self) -> impl std::future::Future<Output = zbus::fdo::Result<()>> + Send {\n        async { Err(zbus::fdo::Error::NotSupported(\"org.example.Complex.GetNothing is not supported\".into())) }\n    }"),
+    );
+}
+
+#[test]
+fn default_bodies_mock_getters_are_fallible() {
+    let options = Options {
+        mock: true,
+        default_bodies: true,
+        ..Options::default()
+    };
+    let actual = generate_server_interface_with_options(XML, &options);
+    common::assert_contains(
+        &actual,
+        "    pi: Option<Box<dyn Fn() -> zbus::fdo::Result<f64> + Send + Sync>>,",
+    );
+}
@@ -0,0 +1,120 @@
+use zbus_xml_gen::{
+    generate_client_proxies_from_xml, generate_from_xml, generate_server_interface_with_options,
+    ContextParams, Dispatch, Mode, Options,
+};
+
+const XML: &str = r#"
+<node name="/org/example">
+  <interface name="org.example.Complex">
+    <method name="WithInputs">
+      <arg name="key" type="s" direction="in"/>
+      <arg name="flag" type="b" direction="in"/>
+      <arg name="result" type="i" direction="out"/>
+    </method>
+    <method name="GetNothing"/>
+    <method name="match">
+      <arg name="value" type="v" direction="in"/>
+      <arg name="result" type="a{sv}" direction="out"/>
+    </method>
+    <signal name="StateChanged">
+      <arg name="state" type="i"/>
+      <arg name="error" type="s"/>
+    </signal>
+    <property name="Status" type="u" access="read"/>
+    <property name="Enabled" type="b" access="readwrite"/>
+    <property name="secret" type="s" access="write"/>
+  </interface>
+  <node name="child">
+    <interface name="org.example.Second">
+      <method name="Ping"/>
+    </interface>
+  </node>
+</node>
+"#;
+
+/// Names the XML allows but Rust does not: stray characters, leading digits,
+/// keywords and quotes in node names.
+const STRAY_XML: &str = r#"
+<node name="/org/&quot;odd&quot;">
+  <interface name="org.example.match">
+    <method name="Run">
+      <arg name="x); drop(y" type="s" direction="in"/>
+      <arg name="2fa" type="s" direction="in"/>
+      <arg name="self" type="s" direction="in"/>
+    </method>
+    <signal name="Done">
+      <arg name="foo-bar" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+fn assert_parses(code: &str) {
+    if let Err(err) = syn::parse_file(code) {
+        panic!(
+            "Generated code does not parse: {}\n---ACTUAL---\n{}\n",
+            err, code
+        );
+    }
+}
+
+fn all_options(dispatch: Dispatch) -> Options {
+    Options {
+        dispatch,
+        signals: true,
+        notifier: true,
+        context: ContextParams {
+            header: true,
+            connection: true,
+            object_server: true,
+            emitter: true,
+        },
+        serve: true,
+        mock: true,
+        default_bodies: true,
+        ..Options::default()
+    }
+}
+
+#[test]
+fn client_output_parses() {
+    assert_parses(&generate_client_proxies_from_xml(XML));
+}
+
+#[test]
+fn server_output_parses() {
+    for dispatch in [Dispatch::Dynamic, Dispatch::Static] {
+        assert_parses(&generate_server_interface_with_options(
+            XML,
+            &Options {
+                dispatch,
+                ..Options::default()
+            },
+        ));
+        assert_parses(&generate_server_interface_with_options(
+            XML,
+            &all_options(dispatch),
+        ));
+    }
+}
+
+#[test]
+fn combined_output_parses() {
+    for dispatch in [Dispatch::Dynamic, Dispatch::Static] {
+        assert_parses(&generate_from_xml(XML, Mode::Both, &all_options(dispatch)));
+    }
+}
+
+#[test]
+fn stray_characters_are_sanitized() {
+    assert_parses(&generate_client_proxies_from_xml(STRAY_XML));
+    assert_parses(&generate_server_interface_with_options(
+        STRAY_XML,
+        &all_options(Dispatch::Dynamic),
+    ));
+    assert_parses(&generate_from_xml(
+        STRAY_XML,
+        Mode::Both,
+        &all_options(Dispatch::Dynamic),
+    ));
+}